@@ -0,0 +1,421 @@
+use std::error;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures_core::Stream;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{lookup_host, TcpSocket, TcpStream};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::{interval, timeout, Interval, MissedTickBehavior};
+
+use crate::ports::StatusPort;
+use crate::rate_limit::RateLimiter;
+use crate::scan::{ScanResult, Target};
+use crate::server_object::{with_sample_cap, ServerStatus};
+use crate::socket_options::SocketOptions;
+use crate::{
+    check_not_minecraft, check_pong_header, check_pong_payload, closed_early, parse_json,
+    ping_packet_builder, validate_address, QueryError, StatusHeader, StatusQuery, VarIntDecoder,
+    TIMEOUT,
+};
+
+async fn var_int_read_async(
+    stream: &mut (impl AsyncRead + Unpin),
+    read_timeout: Duration,
+) -> Result<i32, Box<dyn error::Error + Send + Sync>> {
+    Ok(var_int_read_sized_async(stream, read_timeout).await?.0)
+}
+
+async fn var_int_read_sized_async(
+    stream: &mut (impl AsyncRead + Unpin),
+    read_timeout: Duration,
+) -> Result<(i32, u32), Box<dyn error::Error + Send + Sync>> {
+    // Same as var_int_read_sized, every byte is bounded by the timeout
+    let mut decoder = VarIntDecoder::default();
+    let mut current_byte = [0];
+
+    loop {
+        timeout(read_timeout, stream.read_exact(&mut current_byte)).await??;
+        if let Some(decoded) = decoder.push(current_byte[0])? {
+            return Ok(decoded);
+        }
+    }
+}
+
+async fn connect_async(
+    socket_addrs: &[SocketAddr],
+    connect_timeout: Duration,
+    options: &SocketOptions,
+) -> Result<TcpStream, Box<dyn error::Error + Send + Sync>> {
+    // Tries every resolved address in order, like connect
+    if socket_addrs.is_empty() {
+        return Err("Failed to parse hostname".into());
+    }
+    let mut errors = vec![];
+
+    for socket_addr in socket_addrs {
+        match timeout(connect_timeout, connect_socket(socket_addr, options)).await {
+            Ok(Ok(stream)) => return Ok(stream),
+            Ok(Err(error)) => {
+                if let (Some(port), io::ErrorKind::AddrInUse) = (options.source_port, error.kind())
+                {
+                    return Err(QueryError::SourcePortInUse { port }.into());
+                }
+                errors.push(error);
+            }
+            Err(_) => errors.push(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "connection timed out",
+            )),
+        }
+    }
+
+    Err(QueryError::connect_failed(errors).into())
+}
+
+async fn resolve_async(hostname: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+    // IPv6 literals need brackets to be parsed together with a port
+    if hostname.contains(':') {
+        Ok(lookup_host(format!("[{}]:{}", hostname, port))
+            .await?
+            .collect())
+    } else {
+        Ok(lookup_host(format!("{}:{}", hostname, port))
+            .await?
+            .collect())
+    }
+}
+
+async fn connect_socket(
+    socket_addr: &SocketAddr,
+    options: &SocketOptions,
+) -> io::Result<TcpStream> {
+    // Built like the blocking socket so every option applies, then handed to tokio unconnected
+    let socket = options.socket(socket_addr)?;
+    socket.set_nonblocking(true)?;
+    TcpSocket::from_std_stream(socket.into())
+        .connect(*socket_addr)
+        .await
+}
+
+async fn open_stream_async(
+    query: &StatusQuery,
+    hostname: &str,
+) -> Result<TcpStream, Box<dyn error::Error + Send + Sync>> {
+    if query.proxy.is_some() {
+        return Err("Proxies are only supported by the blocking API".into());
+    }
+    if query.socket_options.connector.is_some() {
+        return Err("Custom connectors are only supported by the blocking API".into());
+    }
+
+    let socket_addrs = match &query.socket_addrs {
+        Some(socket_addrs) => socket_addrs.clone(),
+        None => resolve_async(hostname, query.port).await?,
+    };
+    connect_async(&socket_addrs, query.timeout, &query.socket_options).await
+}
+
+async fn send_status_request_async(
+    stream: &mut TcpStream,
+    query: &StatusQuery,
+    hostname: &str,
+) -> io::Result<()> {
+    for packet in query.status_packets(hostname) {
+        stream.write_all(&packet).await?;
+        stream.flush().await?;
+    }
+    Ok(())
+}
+
+async fn read_status_response_async(
+    stream: &mut TcpStream,
+    query: &StatusQuery,
+) -> Result<Vec<u8>, Box<dyn error::Error + Send + Sync>> {
+    let mut sniffed = [0; 2];
+    timeout(query.timeout, stream.read_exact(&mut sniffed))
+        .await?
+        .map_err(|error| closed_early(error.into()))?;
+    check_not_minecraft(sniffed)?;
+    let mut stream = (&sniffed[..]).chain(stream); // Replays the sniffed bytes
+
+    let frame_length = var_int_read_async(&mut stream, query.timeout)
+        .await
+        .map_err(closed_early)?; // Unpack length from status response
+    let packet_id = var_int_read_sized_async(&mut stream, query.timeout)
+        .await
+        .map_err(closed_early)?; // Unpack id from status response
+    let string_length = var_int_read_sized_async(&mut stream, query.timeout).await?; // Unpack string length from reponse
+    let header = StatusHeader::parse(
+        frame_length,
+        packet_id,
+        string_length,
+        query.max_packet_size,
+    )?;
+
+    read_payload_async(&mut stream, header.string_length, query).await
+}
+
+async fn read_payload_async(
+    stream: &mut (impl AsyncRead + Unpin),
+    string_length: u32,
+    query: &StatusQuery,
+) -> Result<Vec<u8>, Box<dyn error::Error + Send + Sync>> {
+    // Like read_payload, each read is bounded by the timeout and counts against the read call cap
+    let mut buffer = vec![0; string_length as usize]; // Make buffer the size of the string
+    let mut filled = 0;
+    let mut reads = 0;
+    while filled < buffer.len() {
+        if let Some(max_reads) = query.max_read_calls.filter(|max_reads| reads >= *max_reads) {
+            return Err(QueryError::TooManyReads { max_reads }.into());
+        }
+        reads += 1;
+        match timeout(query.timeout, stream.read(&mut buffer[filled..])).await?? {
+            0 => {
+                // Same error as the blocking read_exact
+                let error =
+                    io::Error::new(io::ErrorKind::UnexpectedEof, "failed to fill whole buffer");
+                return Err(error.into());
+            }
+            read => filled += read,
+        }
+    }
+    Ok(buffer)
+}
+
+async fn status_payload_async(
+    query: &StatusQuery,
+) -> Result<String, Box<dyn error::Error + Send + Sync>> {
+    let hostname = validate_address(&query.hostname, query.port)?;
+    let mut stream = open_stream_async(query, &hostname).await?;
+    send_status_request_async(&mut stream, query, &hostname).await?; // Send status request
+
+    let buffer = read_status_response_async(&mut stream, query).await?;
+    query.decode(buffer)
+}
+
+async fn status_value_async(
+    query: &StatusQuery,
+) -> Result<serde_json::Value, Box<dyn error::Error + Send + Sync>> {
+    Ok(serde_json::from_str(&status_payload_async(query).await?)?)
+}
+
+async fn query_ping_async(
+    query: &StatusQuery,
+) -> Result<Duration, Box<dyn error::Error + Send + Sync>> {
+    // Same exchange as query_ping, every read is bounded so a missing pong can't hang the future
+    let hostname = validate_address(&query.hostname, query.port)?;
+    let mut stream = open_stream_async(query, &hostname).await?;
+    send_status_request_async(&mut stream, query, &hostname).await?; // Send status request
+    read_status_response_async(&mut stream, query).await?; // Servers only answer a ping after the status exchange
+
+    let payload = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64;
+    let start = query.clock.now();
+    stream.write_all(&ping_packet_builder(payload)).await?;
+
+    let length = var_int_read_async(&mut stream, query.timeout).await?;
+    let id = var_int_read_async(&mut stream, query.timeout).await?;
+    check_pong_header(length, id)?;
+    let mut echoed = [0; 8];
+    timeout(query.timeout, stream.read_exact(&mut echoed)).await??;
+    check_pong_payload(echoed, payload)?;
+    Ok(query.clock.now() - start)
+}
+
+async fn server_value_async(
+    query: &StatusQuery,
+) -> Result<serde_json::Value, Box<dyn error::Error + Send + Sync>> {
+    let mut json = status_value_async(query).await?;
+
+    if let Some(redirect) = &query.redirect {
+        // Same loop protection as the blocking path
+        let mut redirects = 0;
+        while let Some((hostname, port)) = (redirect.callback)(&json) {
+            if redirects == redirect.max_redirects {
+                return Err("Too many redirects".into());
+            }
+            redirects += 1;
+            json = status_value_async(&query.redirected(&hostname, port)).await?;
+        }
+    }
+
+    Ok(json)
+}
+
+pub(crate) async fn server_json_async(
+    query: &StatusQuery,
+) -> Result<String, Box<dyn error::Error + Send + Sync>> {
+    Ok(server_value_async(query).await?.to_string())
+}
+
+async fn typed_status_async(
+    query: &StatusQuery,
+) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+    // Deserialized once, like the blocking path. Only redirects need the JSON value first.
+    if query.redirect.is_some() {
+        let json = server_value_async(query).await?;
+        return with_sample_cap(query.max_sample_players, || {
+            Ok(serde_json::from_value(json)?)
+        });
+    }
+    let json = status_payload_async(query).await?;
+    with_sample_cap(query.max_sample_players, || parse_json(&json))
+}
+
+pub async fn server_status_async(
+    hostname: &str,
+    port: impl Into<StatusPort>,
+) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+    StatusQuery::new(hostname, port).server_status_async().await
+}
+
+pub async fn get_server_json_async(
+    hostname: &str,
+    port: impl Into<StatusPort>,
+) -> Result<String, Box<dyn error::Error + Send + Sync>> {
+    StatusQuery::new(hostname, port)
+        .get_server_json_async()
+        .await
+}
+
+impl StatusQuery {
+    pub async fn get_server_json_async(
+        &self,
+    ) -> Result<String, Box<dyn error::Error + Send + Sync>> {
+        server_json_async(self).await
+    }
+
+    pub async fn server_status_async(
+        &self,
+    ) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+        typed_status_async(self).await
+    }
+
+    pub async fn ping_async(&self) -> Result<Duration, Box<dyn error::Error + Send + Sync>> {
+        query_ping_async(self).await
+    }
+
+    pub fn poll_async(&self, period: Duration) -> StatusStream {
+        // First status is fetched straight away, then one per period on a fresh connection
+        let mut interval = interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay); // A slow tick doesn't cause a burst after it
+        StatusStream {
+            query: self.clone(),
+            interval,
+            pending: None,
+        }
+    }
+}
+
+type PendingStatus = Pin<Box<dyn Future<Output = Result<ServerStatus, QueryError>> + Send>>;
+
+pub struct StatusStream {
+    query: StatusQuery,
+    interval: Interval,
+    pending: Option<PendingStatus>,
+}
+
+impl Stream for StatusStream {
+    type Item = Result<ServerStatus, QueryError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // Never ends, a failed tick is yielded and the next one tries again
+        let this = self.get_mut();
+        if this.pending.is_none() {
+            ready!(this.interval.poll_tick(cx));
+            let query = this.query.clone();
+            this.pending = Some(Box::pin(async move {
+                match timeout(query.timeout, query.server_status_async()).await {
+                    Ok(status) => status.map_err(QueryError::from_boxed),
+                    Err(_) => {
+                        Err(io::Error::new(io::ErrorKind::TimedOut, "Query timed out").into())
+                    }
+                }
+            }));
+        }
+
+        let status = ready!(this.pending.as_mut().unwrap().as_mut().poll(cx));
+        this.pending = None;
+        Poll::Ready(Some(status))
+    }
+}
+
+async fn batch_target(
+    target: &Target,
+    limiter: Option<&RateLimiter>,
+) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+    let hostname = validate_address(&target.hostname, target.port)?;
+    let socket_addrs = resolve_async(&hostname, target.port).await?;
+    if let (Some(limiter), Some(socket_addr)) = (limiter, socket_addrs.first()) {
+        limiter.wait_async(socket_addr.ip()).await;
+    }
+
+    // The deadline covers the whole query, waiting on the rate limiter doesn't count against it
+    let query = StatusQuery::new(&hostname, target.port).socket_addrs(socket_addrs);
+    timeout(TIMEOUT, query.server_status_async())
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "Query timed out"))?
+}
+
+async fn batch_async(
+    targets: impl IntoIterator<Item = Target>,
+    concurrency: usize,
+    limiter: Option<Arc<RateLimiter>>,
+) -> Vec<ScanResult> {
+    // One task per target, the semaphore bounds how many are connected at once
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+    for target in targets {
+        let semaphore = Arc::clone(&semaphore);
+        let limiter = limiter.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let status = batch_target(&target, limiter.as_deref()).await;
+            (target, status)
+        });
+    }
+
+    let mut results = vec![];
+    while let Some(result) = tasks.join_next().await {
+        if let Ok(result) = result {
+            results.push(result); // Completion order
+        }
+    }
+    results
+}
+
+pub async fn server_status_batch_async(
+    targets: impl IntoIterator<Item = Target>,
+    concurrency: usize,
+) -> Vec<ScanResult> {
+    batch_async(targets, concurrency, None).await
+}
+
+pub async fn server_status_batch_async_limited(
+    targets: impl IntoIterator<Item = Target>,
+    concurrency: usize,
+    limiter: RateLimiter,
+) -> Vec<ScanResult> {
+    batch_async(targets, concurrency, Some(Arc::new(limiter))).await
+}
+
+pub fn poll_async(hostname: &str, port: impl Into<StatusPort>, period: Duration) -> StatusStream {
+    StatusQuery::new(hostname, port).poll_async(period)
+}
+
+pub async fn server_ping_async(
+    hostname: &str,
+    port: impl Into<StatusPort>,
+) -> Result<Duration, Box<dyn error::Error + Send + Sync>> {
+    StatusQuery::new(hostname, port).ping_async().await
+}
@@ -0,0 +1,68 @@
+use std::env;
+use std::error;
+use std::fs;
+use std::process;
+
+use minecraft_query::{ScanClient, Target};
+use serde_json::json;
+
+const USAGE: &str = "Usage: minecraft-query [--input FILE] [host[:port]...]";
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn error::Error + Send + Sync>> {
+    // Addresses come from the arguments and/or a file with one host[:port] per line
+    let mut addresses = vec![];
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => {
+                let path = args.next().ok_or(USAGE)?;
+                let contents = fs::read_to_string(&path)?;
+                addresses.extend(
+                    contents
+                        .lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                        .map(str::to_string),
+                );
+            }
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ => addresses.push(arg),
+        }
+    }
+    if addresses.is_empty() {
+        return Err(USAGE.into());
+    }
+
+    // One JSON object per line, malformed addresses are reported instead of aborting the run
+    let mut targets = vec![];
+    for address in addresses {
+        match Target::parse(&address) {
+            Ok(target) => targets.push(target),
+            Err(error) => println!(
+                "{}",
+                json!({ "address": address, "error": error.to_string() })
+            ),
+        }
+    }
+
+    let client = ScanClient::new(16);
+    for (target, status) in client.scan(targets) {
+        let address = format!("{}:{}", target.hostname, target.port);
+        let line = match status {
+            Ok(status) => json!({ "address": address, "status": status }),
+            Err(error) => json!({ "address": address, "error": error.to_string() }),
+        };
+        println!("{}", line);
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+#[cfg(feature = "tokio")]
+use std::future::{self, Future};
+#[cfg(feature = "tokio")]
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    fn sleep(&self, duration: Duration) {
+        // Waits done by the scanner (rate limiting, jitter) go through here too
+        thread::sleep(duration);
+    }
+
+    #[cfg(feature = "tokio")]
+    fn sleep_async(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    // Lets a test keep a handle on the clock it passed in
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        (**self).sleep(duration)
+    }
+
+    #[cfg(feature = "tokio")]
+    fn sleep_async(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        (**self).sleep_async(duration)
+    }
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+pub struct ManualClock {
+    start: Instant,
+    elapsed: Mutex<Duration>,
+    step: Duration,
+}
+
+impl ManualClock {
+    pub fn new(step: Duration) -> Self {
+        // Only moves when told to, plus step on every reading, so timings come out exact in tests
+        ManualClock {
+            start: Instant::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+            step,
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        let mut elapsed = self.elapsed.lock().unwrap();
+        let now = self.start + *elapsed;
+        *elapsed += self.step;
+        now
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration); // Returns straight away
+    }
+
+    #[cfg(feature = "tokio")]
+    fn sleep_async(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        self.advance(duration);
+        Box::pin(future::ready(()))
+    }
+}
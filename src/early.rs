@@ -0,0 +1,103 @@
+use std::error;
+use std::fmt;
+use std::io::{prelude::*, BufReader};
+
+use serde::de::{Deserializer, IgnoredAny, MapAccess, Visitor};
+
+use crate::query::StatusQuery;
+use crate::server_object::{with_sample_cap, Description, Players, Version};
+use crate::timings::CountingReader;
+use crate::{open_stream, read_status_header, validate_address, Timings};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EarlyStatus {
+    pub version: Version,
+    pub players: Players,
+    pub description: Option<Description>, // Only if it came before version and players were both seen
+    pub complete: bool, // Nothing was left unread, false when the rest (usually the favicon) was abandoned
+}
+
+#[derive(Default)]
+struct EarlyFields {
+    version: Option<Version>,
+    players: Option<Players>,
+    description: Option<Description>,
+    complete: bool,
+}
+
+impl EarlyFields {
+    fn found(&self) -> bool {
+        self.version.is_some() && self.players.is_some()
+    }
+}
+
+struct EarlyVisitor<'a>(&'a mut EarlyFields);
+
+impl<'de> Visitor<'de> for EarlyVisitor<'_> {
+    // Fields are stored as they're seen, serde_json reports an error for the unread rest of the
+    // object once we stop early, so nothing is handed back through the return value.
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a status object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "version" => self.0.version = Some(map.next_value()?),
+                "players" => self.0.players = Some(map.next_value()?),
+                "description" => self.0.description = Some(map.next_value()?),
+                _ => {
+                    map.next_value::<IgnoredAny>()?; // Skipped without being kept, favicon included
+                }
+            }
+            if self.0.found() {
+                break;
+            }
+        }
+        // Costs at most one more key, tells a payload that ended here apart from an abandoned one
+        self.0.complete = map.next_key::<IgnoredAny>()?.is_none();
+        Ok(())
+    }
+}
+
+pub(crate) fn early_status(
+    query: &StatusQuery,
+) -> Result<EarlyStatus, Box<dyn error::Error + Send + Sync>> {
+    // Stops reading as soon as version and players have been parsed. Field order isn't guaranteed,
+    // when they come last this is just a full read (bounded by the size cap) that keeps less.
+    let hostname = validate_address(&query.hostname, query.port)?;
+    let mut stream = open_stream(query, &hostname, &mut Timings::default())?;
+    query.send_status_request(&mut stream, &hostname)?; // Send status request
+
+    let string_length = read_status_header(&mut stream, query.max_packet_size)?.string_length;
+    let mut payload = CountingReader::new(&mut stream);
+    payload.limit_reads(query.max_read_calls);
+    let reader = BufReader::new((&mut payload).take(string_length as u64));
+    let mut fields = EarlyFields::default();
+    let result = with_sample_cap(query.max_sample_players, || {
+        serde_json::Deserializer::from_reader(reader)
+            .deserialize_map(EarlyVisitor(&mut fields))
+            .map_err(Into::into)
+    });
+    let result = payload.limited(result);
+
+    match fields {
+        EarlyFields {
+            version: Some(version),
+            players: Some(players),
+            description,
+            complete,
+        } => Ok(EarlyStatus {
+            version,
+            players,
+            description,
+            complete,
+        }),
+        _ => {
+            result?;
+            Err("Status is missing version or players".into())
+        }
+    }
+}
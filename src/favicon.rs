@@ -0,0 +1,80 @@
+use std::error;
+
+use crate::server_object::ServerStatus;
+
+const PREFIX: &str = "data:image/png;base64,";
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+pub(crate) fn base64_decode(encoded: &str) -> Option<Vec<u8>> {
+    // Standard alphabet, servers often wrap the favicon with newlines so whitespace is skipped
+    let mut decoded = vec![];
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in encoded.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            byte if byte.is_ascii_whitespace() => continue,
+            _ => return None,
+        };
+        buffer = buffer << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+pub(crate) fn png_dimensions(png: &[u8]) -> Option<(u32, u32)> {
+    // Width and height from the IHDR chunk, which always comes first
+    if png.len() < 24 || png[..8] != PNG_SIGNATURE || &png[12..16] != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(png[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(png[20..24].try_into().ok()?);
+    Some((width, height))
+}
+
+impl ServerStatus {
+    pub fn favicon_bytes(&self) -> Result<Option<Vec<u8>>, Box<dyn error::Error + Send + Sync>> {
+        // The favicon is a data URI, None when the server doesn't have one
+        if self.favicon.is_empty() {
+            return Ok(None);
+        }
+        let encoded = self
+            .favicon
+            .strip_prefix(PREFIX)
+            .ok_or("Favicon isn't a base64 PNG data URI")?;
+        let png = base64_decode(encoded).ok_or("Favicon has invalid base64")?;
+        Ok(Some(png))
+    }
+
+    pub fn favicon_is_spec_compliant(&self) -> Option<bool> {
+        // Clients only render 64x64 PNGs, only the IHDR is read. None when there's no favicon.
+        match self.favicon_bytes() {
+            Ok(Some(png)) => Some(png_dimensions(&png) == Some((64, 64))),
+            Ok(None) => None,
+            Err(_) => Some(false),
+        }
+    }
+
+    #[cfg(feature = "image")]
+    pub fn favicon_image(
+        &self,
+    ) -> Result<Option<image::DynamicImage>, Box<dyn error::Error + Send + Sync>> {
+        // Off-spec sizes still decode, parse_json_lenient is where they get flagged
+        match self.favicon_bytes()? {
+            Some(png) => Ok(Some(image::load_from_memory_with_format(
+                &png,
+                image::ImageFormat::Png,
+            )?)),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use std::error;
+use std::io::Read;
+
+use crate::frame::FrameReader;
+use crate::server_object::{ForgeData, ForgeMod};
+use crate::var_int_read;
+
+// What Forge sends instead of a version for mods that don't care whether the client has them
+const IGNORE_SERVER_ONLY: &str = "OHNOES\u{1F631}\u{1F631}\u{1F631}\u{1F631}";
+
+fn decode_bytes(d: &str) -> Result<Vec<u8>, Box<dyn error::Error + Send + Sync>> {
+    // Each char carries 15 bits, the first two hold the byte length. Every char is below 0x8000, so
+    // this survives JSON and Java's UTF-16 strings. Mirrors ServerStatusPing.decodeOptimized.
+    let chars = d.chars().map(|c| c as u32).collect::<Vec<_>>();
+    if chars.len() < 2 || chars.iter().any(|c| *c > 0x7FFF) {
+        return Err("Invalid forgeData \"d\" string".into());
+    }
+    let length = (chars[0] | chars[1] << 15) as usize;
+
+    let mut bytes = Vec::with_capacity(length.min(chars.len() * 2));
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in &chars[2..] {
+        // Surplus chars after the announced length are padding, reading on would overflow the shift
+        if bytes.len() == length {
+            break;
+        }
+        buffer |= c << bits;
+        bits += 15;
+        while bits >= 8 && bytes.len() < length {
+            bytes.push(buffer as u8);
+            buffer >>= 8;
+            bits -= 8;
+        }
+    }
+    if bytes.len() < length {
+        return Err("forgeData \"d\" is shorter than its announced length".into());
+    }
+    Ok(bytes)
+}
+
+fn read_string(bytes: &mut &[u8]) -> Result<String, Box<dyn error::Error + Send + Sync>> {
+    let remaining = bytes.len() as u32;
+    Ok(String::from_utf8(
+        FrameReader::new(bytes, Some(remaining)).read_frame()?,
+    )?)
+}
+
+fn read_u8(bytes: &mut &[u8]) -> Result<u8, Box<dyn error::Error + Send + Sync>> {
+    let mut byte = [0; 1];
+    bytes.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+pub(crate) fn decode_optimized(
+    d: &str,
+) -> Result<(bool, Vec<ForgeMod>), Box<dyn error::Error + Send + Sync>> {
+    // Returns the truncated flag and the mods, the channel list after them isn't needed
+    let bytes = decode_bytes(d)?;
+    let mut bytes = bytes.as_slice();
+
+    let truncated = read_u8(&mut bytes)? != 0;
+    let mod_count = u16::from_be_bytes([read_u8(&mut bytes)?, read_u8(&mut bytes)?]);
+    let mut mods = vec![];
+    for _ in 0..mod_count {
+        let channels_and_flag = var_int_read(&mut bytes)?;
+        let mod_id = read_string(&mut bytes)?;
+        let mod_marker = match channels_and_flag & 1 {
+            1 => IGNORE_SERVER_ONLY.to_string(),
+            _ => read_string(&mut bytes)?,
+        };
+        for _ in 0..channels_and_flag >> 1 {
+            read_string(&mut bytes)?; // Channel name
+            read_string(&mut bytes)?; // Channel version
+            read_u8(&mut bytes)?; // Required on the client
+        }
+        mods.push(ForgeMod { mod_id, mod_marker });
+    }
+    Ok((truncated, mods))
+}
+
+impl ForgeData {
+    pub fn all_mods(&self) -> Result<Vec<ForgeMod>, Box<dyn error::Error + Send + Sync>> {
+        // Forge 1.18.2+ (FML3) leaves "mods" empty and packs the list into "d" instead. Even that can
+        // be truncated when there are too many mods for one packet, see is_truncated.
+        match &self.d {
+            Some(d) => Ok(decode_optimized(d)?.1),
+            None => Ok(self.mods.clone()),
+        }
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+            || self
+                .d
+                .as_deref()
+                .is_some_and(|d| decode_optimized(d).is_ok_and(|(truncated, _)| truncated))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::var_int_pack;
+
+    fn encode_optimized(bytes: &[u8]) -> String {
+        // ServerStatusPing.encodeOptimized, the other direction
+        let mut chars = vec![
+            bytes.len() as u32 & 0x7FFF,
+            (bytes.len() as u32 >> 15) & 0x7FFF,
+        ];
+        let mut buffer: u32 = 0;
+        let mut bits = 0;
+        for byte in bytes {
+            if bits >= 15 {
+                chars.push(buffer & 0x7FFF);
+                buffer >>= 15;
+                bits -= 15;
+            }
+            buffer |= (*byte as u32) << bits;
+            bits += 8;
+        }
+        if bits > 0 {
+            chars.push(buffer & 0x7FFF);
+        }
+        chars.into_iter().filter_map(char::from_u32).collect()
+    }
+
+    #[test]
+    fn optimized_mod_list() {
+        let bytes = [
+            vec![0x01, 0x00, 0x02],
+            vec![0x02], // One channel, has a version
+            var_int_pack(b"jei".to_vec()),
+            var_int_pack(b"11.6.0".to_vec()),
+            var_int_pack(b"main".to_vec()),
+            var_int_pack(b"1".to_vec()),
+            vec![0x01],
+            vec![0x01], // No channels, ignores server only
+            var_int_pack(b"pingwheel".to_vec()),
+            vec![0x00], // No other channels
+        ]
+        .concat();
+        let forge_data: ForgeData = serde_json::from_value(serde_json::json!({
+            "fmlNetworkVersion": 3,
+            "mods": [],
+            "d": encode_optimized(&bytes),
+        }))
+        .unwrap();
+
+        let mods = forge_data.all_mods().unwrap();
+        assert_eq!(mods.len(), 2);
+        assert_eq!(
+            (mods[0].mod_id.as_str(), mods[0].mod_marker.as_str()),
+            ("jei", "11.6.0")
+        );
+        assert_eq!(mods[1].mod_marker, IGNORE_SERVER_ONLY);
+        assert!(forge_data.is_truncated());
+
+        assert!(decode_optimized(&encode_optimized(&bytes[..10])).is_err());
+    }
+
+    #[test]
+    fn trailing_chars() {
+        assert_eq!(decode_bytes("\u{1}\u{0}aaaaa").unwrap(), vec![b'a']);
+        assert!(decode_bytes("\u{0}\u{0}aaaaa").unwrap().is_empty());
+    }
+}
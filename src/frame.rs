@@ -0,0 +1,66 @@
+use std::error;
+use std::io::Read;
+
+use crate::{check_string_length, var_int_read_sized};
+
+pub(crate) struct FrameReader<R> {
+    inner: R,
+    max_size: Option<u32>, // Applies to every length read through this reader
+}
+
+impl<R: Read> FrameReader<R> {
+    pub(crate) fn new(inner: R, max_size: Option<u32>) -> Self {
+        FrameReader { inner, max_size }
+    }
+
+    pub(crate) fn read_varint(&mut self) -> Result<i32, Box<dyn error::Error + Send + Sync>> {
+        Ok(self.read_varint_sized()?.0)
+    }
+
+    pub(crate) fn read_varint_sized(
+        &mut self,
+    ) -> Result<(i32, u32), Box<dyn error::Error + Send + Sync>> {
+        // The value and how many bytes it was encoded in
+        var_int_read_sized(&mut self.inner)
+    }
+
+    pub(crate) fn read_length(
+        &mut self,
+    ) -> Result<(u32, u32), Box<dyn error::Error + Send + Sync>> {
+        // A VarInt length prefix, rejected when negative or over the cap before anything is allocated
+        let (length, size) = self.read_varint_sized()?;
+        Ok((check_string_length(length, self.max_size)?, size))
+    }
+
+    pub(crate) fn read_frame(&mut self) -> Result<Vec<u8>, Box<dyn error::Error + Send + Sync>> {
+        // Length prefix, then exactly that many bytes. Also reads length prefixed strings.
+        let (length, _) = self.read_length()?;
+        let mut frame = vec![0; length as usize];
+        self.inner.read_exact(&mut frame)?;
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::var_int_pack;
+
+    #[test]
+    fn frames() {
+        let bytes = [var_int_pack(b"abc".to_vec()), vec![0x80, 0x01]].concat();
+        let mut reader = FrameReader::new(bytes.as_slice(), Some(3));
+        assert_eq!(reader.read_frame().unwrap(), b"abc");
+        assert_eq!(reader.read_varint_sized().unwrap(), (128, 2));
+        assert!(reader.read_varint().is_err()); // Nothing left
+
+        let too_long = var_int_pack(b"abcd".to_vec());
+        assert!(FrameReader::new(too_long.as_slice(), Some(3))
+            .read_frame()
+            .is_err());
+        let negative = [0xFF, 0xFF, 0xFF, 0xFF, 0x0F];
+        assert!(FrameReader::new(&negative[..], None).read_frame().is_err());
+        let short = [0x05, b'a'];
+        assert!(FrameReader::new(&short[..], None).read_frame().is_err());
+    }
+}
@@ -0,0 +1,40 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandshakeStyle {
+    // What vanilla clients send, the bare hostname with nothing appended. Older BungeeCord setups
+    // without IP forwarding reject anything else.
+    Vanilla,
+    // BungeeCord legacy IP forwarding, "<host>\0<ip>\0<uuid>" plus "\0<properties json>" when given
+    BungeeForwarding {
+        client_ip: String,
+        uuid: String,
+        properties: Option<String>,
+    },
+    // Velocity modern forwarding happens during login, the status handshake is vanilla
+    VelocityModern,
+}
+
+impl HandshakeStyle {
+    pub(crate) fn fields(&self) -> Vec<String> {
+        match self {
+            HandshakeStyle::Vanilla | HandshakeStyle::VelocityModern => vec![],
+            HandshakeStyle::BungeeForwarding {
+                client_ip,
+                uuid,
+                properties,
+            } => [client_ip, uuid]
+                .into_iter()
+                .chain(properties)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn hostname(&self, hostname: &str) -> String {
+        // The exact hostname string sent in the handshake, fields are null separated with no trailing null
+        [hostname.to_string()]
+            .into_iter()
+            .chain(self.fields())
+            .collect::<Vec<_>>()
+            .join("\0")
+    }
+}
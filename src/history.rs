@@ -0,0 +1,56 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::server_object::ServerStatus;
+
+#[derive(Debug, Clone)]
+pub struct StatusHistory {
+    retention: Duration,
+    samples: VecDeque<(Instant, i64)>,
+}
+
+impl StatusHistory {
+    pub fn new(retention: Duration) -> Self {
+        // Samples older than retention (relative to the newest one) are dropped as new ones arrive
+        StatusHistory {
+            retention,
+            samples: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, status: &ServerStatus, at: Instant) {
+        // Polls are expected in order, an older timestamp is kept but can be pruned straight away
+        self.samples.push_back((at, status.players.online));
+        let newest = self.samples.iter().map(|(at, _)| *at).max().unwrap_or(at);
+        self.samples
+            .retain(|(at, _)| newest.saturating_duration_since(*at) <= self.retention);
+    }
+
+    pub fn online_series(&self) -> Vec<(Instant, i64)> {
+        self.samples.iter().copied().collect()
+    }
+
+    pub fn min(&self) -> Option<i64> {
+        self.samples.iter().map(|(_, online)| *online).min()
+    }
+
+    pub fn max(&self) -> Option<i64> {
+        self.samples.iter().map(|(_, online)| *online).max()
+    }
+
+    pub fn average(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let total: i64 = self.samples.iter().map(|(_, online)| online).sum();
+        Some(total as f64 / self.samples.len() as f64)
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
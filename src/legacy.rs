@@ -0,0 +1,128 @@
+use std::error;
+use std::io::prelude::*;
+
+use crate::ports::StatusPort;
+use crate::socket_options::SocketOptions;
+use crate::{connect, resolve, validate_address, QueryError, TIMEOUT};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyStatus {
+    pub protocol: Option<i64>, // Only sent by 1.4+ servers
+    pub version: Option<String>,
+    pub motd: String,
+    pub online: i64,
+    pub max: i64,
+}
+
+fn utf16_be(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(u16::to_be_bytes).collect()
+}
+
+fn legacy_ping_packet(hostname: &str, port: u16) -> Vec<u8> {
+    // 1.6 form, https://wiki.vg/Server_List_Ping#1.6. Older servers stop reading after 0xFE and
+    // answer in their own format, so this one request covers everything from Beta 1.8 on.
+    let channel = "MC|PingHost";
+    let hostname_length = hostname.encode_utf16().count() as u16;
+    [
+        vec![0xFE, 0x01, 0xFA],
+        (channel.len() as u16).to_be_bytes().to_vec(),
+        utf16_be(channel),
+        (7 + 2 * hostname_length).to_be_bytes().to_vec(),
+        vec![74], // Protocol of 1.6.2, the last version that used this ping
+        hostname_length.to_be_bytes().to_vec(),
+        utf16_be(hostname),
+        (port as i32).to_be_bytes().to_vec(),
+    ]
+    .concat()
+}
+
+fn parse_legacy_response(response: &str) -> Result<LegacyStatus, QueryError> {
+    let invalid = |reason: &str| QueryError::InvalidResponse {
+        reason: reason.to_string(),
+    };
+    let number = |field: &str| {
+        field
+            .parse()
+            .map_err(|_| invalid("non numeric player count"))
+    };
+
+    if let Some(fields) = response.strip_prefix("§1\0") {
+        // 1.4+: protocol, version, motd, online and max, separated by nulls
+        let fields: Vec<&str> = fields.split('\0').collect();
+        if let [protocol, version, motd, online, max] = fields[..] {
+            return Ok(LegacyStatus {
+                protocol: Some(number(protocol)?),
+                version: Some(version.to_string()),
+                motd: motd.to_string(),
+                online: number(online)?,
+                max: number(max)?,
+            });
+        }
+        return Err(invalid("expected 5 fields in the legacy response"));
+    }
+
+    // Beta 1.8 to 1.3: motd§online§max, the motd itself can't contain §
+    let mut fields = response.rsplitn(3, '§');
+    match (fields.next(), fields.next(), fields.next()) {
+        (Some(max), Some(online), Some(motd)) => Ok(LegacyStatus {
+            protocol: None,
+            version: None,
+            motd: motd.to_string(),
+            online: number(online)?,
+            max: number(max)?,
+        }),
+        _ => Err(invalid("expected 3 fields in the legacy response")),
+    }
+}
+
+pub fn legacy_ping(
+    hostname: &str,
+    port: impl Into<StatusPort>,
+) -> Result<LegacyStatus, Box<dyn error::Error + Send + Sync>> {
+    // For pre-1.7 servers that don't understand the modern handshake
+    let StatusPort(port) = port.into();
+    let hostname = validate_address(hostname, port)?;
+    let socket_addrs = resolve(&hostname, port)?;
+    let mut stream = connect(&socket_addrs, TIMEOUT, &SocketOptions::default())?;
+    stream.write_all(&legacy_ping_packet(&hostname, port))?;
+
+    // The answer is a kick packet, 0xFF then a UTF-16BE string prefixed with its length in characters
+    let mut header = [0; 3];
+    stream.read_exact(&mut header)?;
+    if header[0] != 0xFF {
+        return Err(QueryError::InvalidResponse {
+            reason: format!("expected a kick packet, got {:#04x}", header[0]),
+        }
+        .into());
+    }
+    let mut buffer = vec![0; u16::from_be_bytes([header[1], header[2]]) as usize * 2];
+    stream.read_exact(&mut buffer)?;
+
+    let units: Vec<u16> = buffer
+        .chunks_exact(2)
+        .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+        .collect();
+    Ok(parse_legacy_response(&String::from_utf16(&units)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_formats() {
+        let status =
+            parse_legacy_response("§1\u{0}74\u{0}1.6.2\u{0}A Server\u{0}3\u{0}20").unwrap();
+        assert_eq!(status.protocol, Some(74));
+        assert_eq!(status.version.as_deref(), Some("1.6.2"));
+        assert_eq!((status.online, status.max), (3, 20));
+
+        let status = parse_legacy_response("Old Server§1§10").unwrap();
+        assert_eq!(status.motd, "Old Server");
+        assert_eq!(status.protocol, None);
+        assert_eq!((status.online, status.max), (1, 10));
+
+        assert!(parse_legacy_response("garbage").is_err());
+        assert_eq!(legacy_ping_packet("a", 25565)[30..32], [0x00, 0x01]); // Hostname length
+    }
+}
@@ -0,0 +1,276 @@
+use std::error;
+use std::fmt;
+
+use serde_json::{json, Map, Value};
+
+use crate::favicon::{base64_decode, png_dimensions};
+use crate::server_object::{nested_favicon, sample_cap, ForgeData, ModInfo, Sample, ServerStatus};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.field, self.message)
+    }
+}
+
+struct Recoveries(Vec<ParseWarning>);
+
+impl Recoveries {
+    fn warn(&mut self, field: &str, message: &str) {
+        self.0.push(ParseWarning {
+            field: field.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    fn object<'a>(
+        &mut self,
+        parent: &'a mut Map<String, Value>,
+        field: &str,
+        path: &str,
+    ) -> &'a mut Map<String, Value> {
+        // Makes sure parent[field] is an object, replacing whatever else was there
+        match parent.get(field) {
+            Some(Value::Object(_)) => {}
+            Some(_) => self.warn(path, "was not an object, replaced with an empty one"),
+            None => self.warn(path, "missing, defaulted to an empty object"),
+        }
+        if !parent.get(field).is_some_and(Value::is_object) {
+            parent.insert(field.to_string(), json!({}));
+        }
+        parent
+            .get_mut(field)
+            .and_then(Value::as_object_mut)
+            .unwrap()
+    }
+
+    fn integer(&mut self, parent: &mut Map<String, Value>, field: &str, path: &str) {
+        // Integers stay, numeric strings and floats are coerced, anything else becomes 0
+        let coerced = match parent.get(field) {
+            Some(value) if value.is_i64() => return,
+            Some(Value::String(string)) if string.trim().parse::<i64>().is_ok() => {
+                self.warn(path, "was a string, coerced to an integer");
+                string.trim().parse::<i64>().unwrap()
+            }
+            Some(Value::Number(number)) => {
+                self.warn(path, "was not an integer, truncated");
+                number.as_f64().unwrap_or(0.0) as i64
+            }
+            Some(_) => {
+                self.warn(path, "was not a number, defaulted to 0");
+                0
+            }
+            None => {
+                self.warn(path, "missing, defaulted to 0");
+                0
+            }
+        };
+        parent.insert(field.to_string(), json!(coerced));
+    }
+
+    fn string(&mut self, parent: &mut Map<String, Value>, field: &str, path: &str) {
+        // Scalars are stringified, anything else becomes an empty string
+        let coerced = match parent.get(field) {
+            Some(Value::String(_)) => return,
+            Some(value @ (Value::Number(_) | Value::Bool(_))) => {
+                self.warn(path, "was not a string, coerced to one");
+                value.to_string()
+            }
+            Some(_) => {
+                self.warn(path, "was not a string, defaulted to an empty one");
+                String::new()
+            }
+            None => {
+                self.warn(path, "missing, defaulted to an empty string");
+                String::new()
+            }
+        };
+        parent.insert(field.to_string(), json!(coerced));
+    }
+}
+
+pub fn parse_json_lenient(
+    json: &str,
+) -> Result<(ServerStatus, Vec<ParseWarning>), Box<dyn error::Error + Send + Sync>> {
+    // Repairs whatever it can before deserializing, and reports each repair instead of failing the parse.
+    let mut value: Value = serde_json::from_str(json)?;
+    let root = value.as_object_mut().ok_or("Status is not a JSON object")?;
+    let mut recoveries = Recoveries(vec![]);
+
+    match root.get("description") {
+        Some(Value::String(text)) => {
+            let text = text.clone(); // A bare string is a valid chat component
+            root.insert("description".into(), json!({ "text": text }));
+        }
+        Some(Value::Number(_) | Value::Bool(_)) => {
+            let text = root["description"].to_string();
+            recoveries.warn(
+                "description",
+                "was not text or an object, coerced to a string",
+            );
+            root.insert("description".into(), json!({ "text": text }));
+        }
+        _ => {}
+    }
+    let description = recoveries.object(root, "description", "description");
+    if description.contains_key("text") {
+        recoveries.string(description, "text", "description.text"); // Optional, e.g. translatable components
+    }
+
+    let players = recoveries.object(root, "players", "players");
+    recoveries.integer(players, "max", "players.max");
+    recoveries.integer(players, "online", "players.online");
+    match players.get_mut("sample") {
+        Some(Value::Array(sample)) => {
+            let cap = sample_cap();
+            if sample.len() > cap {
+                sample.truncate(cap); // Before validating, so a huge sample isn't cloned and parsed in full
+                recoveries.warn(
+                    "players.sample",
+                    &format!("had more than {} entries, the rest were dropped", cap),
+                );
+            }
+            let before = sample.len();
+            sample.retain(|entry| serde_json::from_value::<Sample>(entry.clone()).is_ok());
+            if sample.len() != before {
+                recoveries.warn("players.sample", "had malformed entries, they were dropped");
+            }
+        }
+        Some(_) => {
+            players.remove("sample");
+            recoveries.warn("players.sample", "was not an array, ignored");
+        }
+        None => {}
+    }
+
+    if let Some(Value::Array(versions)) = root.get("version") {
+        let primary = versions.first().cloned().unwrap_or_else(|| json!({}));
+        let alternatives = versions
+            .iter()
+            .skip(1)
+            .map(|version| match version {
+                Value::String(name) => name.clone(),
+                version => version
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .map_or_else(|| version.to_string(), str::to_string),
+            })
+            .collect::<Vec<_>>();
+        recoveries.warn(
+            "version",
+            &format!(
+                "was a list, using the first entry (alternatives: {})",
+                alternatives.join(", ")
+            ),
+        );
+        root.insert("version".into(), primary);
+    }
+    if !root.get("version").is_some_and(Value::is_string) {
+        let version = recoveries.object(root, "version", "version");
+        recoveries.string(version, "name", "version.name");
+        recoveries.integer(version, "protocol", "version.protocol");
+    }
+
+    if let Some(icon) = root.remove("icon") {
+        // Some forks and proxies send "icon", "favicon" wins when both are there
+        if root.contains_key("favicon") {
+            recoveries.warn("favicon", "was sent along with \"icon\", ignored the icon");
+        } else {
+            root.insert("favicon".into(), icon);
+            recoveries.warn("favicon", "was sent as \"icon\", used that");
+        }
+    }
+    if let Some(favicon) = root.get("favicon").filter(|favicon| !favicon.is_string()) {
+        match nested_favicon(favicon).map(str::to_string) {
+            Some(nested) => {
+                root.insert("favicon".into(), json!(nested));
+                recoveries.warn("favicon", "was a nested object, used its data");
+            }
+            None => {
+                root.remove("favicon");
+                recoveries.warn("favicon", "was not a string, ignored");
+            }
+        }
+    }
+    if let Some(favicon) = root.get("favicon").and_then(|favicon| favicon.as_str()) {
+        // Kept either way, clients just scale or refuse it
+        let png = favicon
+            .strip_prefix("data:image/png;base64,")
+            .and_then(base64_decode);
+        match png.as_deref().and_then(png_dimensions) {
+            Some((64, 64)) => {}
+            Some((width, height)) => recoveries.warn(
+                "favicon",
+                &format!("is {}x{}, expected 64x64", width, height),
+            ),
+            None if !favicon.is_empty() => recoveries.warn("favicon", "is not a base64 PNG"),
+            None => {}
+        }
+    }
+
+    if root
+        .get("forgeData")
+        .is_some_and(|forge_data| serde_json::from_value::<ForgeData>(forge_data.clone()).is_err())
+    {
+        root.remove("forgeData");
+        recoveries.warn("forgeData", "was malformed, ignored");
+    }
+    if root
+        .get("modinfo")
+        .is_some_and(|modinfo| serde_json::from_value::<ModInfo>(modinfo.clone()).is_err())
+    {
+        root.remove("modinfo");
+        recoveries.warn("modinfo", "was malformed, ignored");
+    }
+
+    for field in ["enforcesSecureChat", "previewsChat"] {
+        match root.get(field) {
+            Some(Value::Bool(_)) | None => {}
+            Some(Value::String(flag))
+                if flag.trim().eq_ignore_ascii_case("true")
+                    || flag.trim().eq_ignore_ascii_case("false") =>
+            {
+                recoveries.warn(field, "was a string, coerced")
+            }
+            Some(_) => {
+                root.remove(field);
+                recoveries.warn(field, "was not a boolean, ignored");
+            }
+        }
+    }
+
+    Ok((serde_json::from_value(value)?, recoveries.0))
+}
+
+#[derive(Debug)]
+pub struct ParseReport {
+    pub status: ServerStatus, // The strict parse when it succeeded, the lenient one otherwise
+    pub strict_error: Option<serde_json::Error>,
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl ParseReport {
+    pub fn is_conformant(&self) -> bool {
+        self.strict_error.is_none()
+    }
+}
+
+pub fn try_parse(json: &str) -> Result<ParseReport, Box<dyn error::Error + Send + Sync>> {
+    // Strict and lenient side by side, so a non-conformant status still comes with the data recovered from it.
+    // Only fails when even the lenient parse can't make sense of the JSON.
+    let (lenient, warnings) = parse_json_lenient(json)?;
+    let (status, strict_error) = match serde_json::from_str::<ServerStatus>(json) {
+        Ok(status) => (status, None),
+        Err(error) => (lenient, Some(error)),
+    };
+    Ok(ParseReport {
+        status,
+        strict_error,
+        warnings,
+    })
+}
@@ -1,18 +1,99 @@
 use std::error;
-use std::io::prelude::*;
-use std::net::{TcpStream, ToSocketAddrs};
-use std::time::Duration;
+use std::io::{self, prelude::*, BufReader};
+use std::net::{Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use serde::de::DeserializeOwned;
+
+#[cfg(feature = "tokio")]
+mod async_query;
+mod clock;
+mod early;
+mod favicon;
+mod forge;
+mod frame;
+mod handshake;
+mod history;
+mod legacy;
+mod lenient;
+mod login;
+mod motd;
+mod network_filter;
+mod outcome;
+mod plausibility;
+mod ports;
+mod proxy;
+mod proxy_protocol;
+mod query;
+mod query_error;
+mod rate_limit;
+mod render;
+mod scan;
+mod server_list;
 mod server_object;
-use server_object::ServerStatus;
+mod socket_options;
+mod software;
+mod status_v1;
+mod timings;
+mod transcript;
+mod udp_query;
+mod vhost;
+#[cfg(feature = "tokio")]
+pub use async_query::{
+    get_server_json_async, poll_async, server_ping_async, server_status_async,
+    server_status_batch_async, server_status_batch_async_limited, StatusStream,
+};
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use early::EarlyStatus;
+use frame::FrameReader;
+pub use handshake::HandshakeStyle;
+pub use history::StatusHistory;
+pub use legacy::{legacy_ping, LegacyStatus};
+pub use lenient::{parse_json_lenient, try_parse, ParseReport, ParseWarning};
+pub use login::{login_check, LoginMode};
+pub use motd::{McColor, Span};
+pub use network_filter::{Cidr, NetworkFilter};
+pub use outcome::{scan_outcome, ScanOutcome};
+pub use plausibility::Plausibility;
+pub use ports::{BedrockPort, QueryPort, StatusPort};
+pub use proxy::Proxy;
+pub use proxy_protocol::ProxyProtocol;
+pub use query::{RedirectCallback, StatusQuery};
+pub use query_error::QueryError;
+pub use rate_limit::RateLimiter;
+pub use render::{Ansi, Html, Markdown, MotdRenderer, PlainText};
+pub use scan::{Pacing, ScanClient, ScanResult, Target, TypedScanResult};
+#[cfg(feature = "nbt")]
+pub use server_list::servers_dat;
+pub use server_list::ServerListEntry;
+use server_object::with_sample_cap;
+pub use server_object::{
+    protocol_to_version, ChatSecurity, ClickEvent, Component, Description, ForgeData, ForgeMod,
+    ModInfo, ModInfoEntry, Players, Sample, ServerStatus, Version,
+};
+pub use socket_options::Connector;
+use socket_options::SocketOptions;
+pub use software::{GeyserInfo, ServerSoftware};
+pub use status_v1::{ModV1, PlayerV1, StatusV1};
+pub use timings::Timings;
+use timings::{timed, CountingReader};
+use transcript::Recorder;
+pub use transcript::Transcript;
+pub use udp_query::{query_full_stat, server_info, FullStat, ServerInfo};
+pub use vhost::{query_vhosts, VhostResults};
 
+pub const DEFAULT_PORT: u16 = 25565;
+pub const DEFAULT_QUERY_PORT: u16 = DEFAULT_PORT; // query.port in server.properties, shares the game port unless changed
+pub const DEFAULT_BEDROCK_PORT: u16 = 19132;
+const DEFAULT_PROTOCOL: i32 = -1; // What clients send when pinging without knowing the server's version
 const TIMEOUT: Duration = Duration::from_secs(5);
 const MAX_PACKET_SIZE: u32 = 1024 * 1024 * 50; // Limit the reponse to 50MB
+pub const MAX_SAMPLE_PLAYERS: usize = 1000; // Sample entries kept per status, the rest are skipped while parsing
 
 fn var_int_encode(num: i32) -> Vec<u8> {
     // Encodes into VarInt, https://wiki.vg/VarInt_And_VarLong
     let mut var_int = vec![];
-    let mut value = num;
+    let mut value = num as u32; // Negative numbers use all 5 bytes, so shift as unsigned
 
     while value >= 0x80 {
         var_int.push(0x80 | (value as u8));
@@ -23,26 +104,50 @@ fn var_int_encode(num: i32) -> Vec<u8> {
     var_int
 }
 
-fn var_int_read(stream: &mut TcpStream) -> Result<i32, Box<dyn error::Error>> {
-    // Reads VarInt from stream, https://wiki.vg/VarInt_And_VarLong
-    let mut value: i32 = 0;
-    let mut length = 0;
-    let mut current_byte = vec![0];
+fn var_int_read(stream: &mut impl Read) -> Result<i32, Box<dyn error::Error + Send + Sync>> {
+    Ok(var_int_read_sized(stream)?.0)
+}
+
+fn var_int_read_sized(
+    stream: &mut impl Read,
+) -> Result<(i32, u32), Box<dyn error::Error + Send + Sync>> {
+    // Reads VarInt from stream along with how many bytes it took
+    let mut decoder = VarIntDecoder::default();
+    let mut current_byte = [0];
 
     loop {
         stream.read_exact(&mut current_byte)?;
-        value |= (current_byte[0] as i32 & 0x7F)
-            .checked_shl(length * 7)
+        if let Some(decoded) = decoder.push(current_byte[0])? {
+            return Ok(decoded);
+        }
+    }
+}
+
+#[derive(Default)]
+struct VarIntDecoder {
+    value: i32,
+    length: u32,
+}
+
+impl VarIntDecoder {
+    fn push(
+        &mut self,
+        byte: u8,
+    ) -> Result<Option<(i32, u32)>, Box<dyn error::Error + Send + Sync>> {
+        // https://wiki.vg/VarInt_And_VarLong, fed one byte at a time so the blocking and async readers decode alike.
+        // Over-long encodings (0x80 0x00 for 0) are accepted like vanilla does, only more than 5 bytes is an error.
+        self.value |= (byte as i32 & 0x7F)
+            .checked_shl(self.length * 7)
             .unwrap_or(0);
-        length += 1;
-        if length > 5 {
+        self.length += 1;
+        if self.length > 5 {
             return Err("Server's reponse had invaild VarInt".into());
         }
-        if (current_byte[0] & 0x80) != 0x80 {
-            break;
+        if (byte & 0x80) != 0x80 {
+            return Ok(Some((self.value, self.length)));
         }
+        Ok(None)
     }
-    Ok(value)
 }
 
 fn var_int_pack(data: Vec<u8>) -> Vec<u8> {
@@ -52,66 +157,836 @@ fn var_int_pack(data: Vec<u8>) -> Vec<u8> {
     packed
 }
 
-fn status_packet_builder(hostname: &str, port: u16) -> Vec<u8> {
-    // Builds a proper status ping, requires hostname and port because of the protocol.
-    vec![
-        var_int_pack(
-            [
-                vec![0x00, 0x00],
-                var_int_pack(hostname.as_bytes().to_vec()),
-                port.to_be_bytes().to_vec(),
-                vec![0x01],
-            ]
-            .into_iter()
-            .flatten()
-            .collect(),
-        ),
-        var_int_pack(vec![0x00]),
+pub fn build_handshake(protocol: i32, hostname: &str, port: u16, next_state: u8) -> Vec<u8> {
+    // Builds a framed handshake packet, next_state is 1 for status and 2 for login.
+    var_int_pack(
+        [
+            vec![0x00], // Packet id
+            var_int_encode(protocol),
+            var_int_pack(hostname.as_bytes().to_vec()),
+            port.to_be_bytes().to_vec(),
+            vec![next_state],
+        ]
+        .into_iter()
+        .flatten()
+        .collect(),
+    )
+}
+
+fn status_request_builder() -> Vec<u8> {
+    // The status request is an empty packet with id 0x00
+    var_int_pack(vec![0x00])
+}
+
+fn status_packet_builder(protocol: i32, hostname: &str, port: u16) -> Vec<u8> {
+    // Builds a proper status ping, the handshake followed by the status request.
+    [
+        build_handshake(protocol, hostname, port, 0x01),
+        status_request_builder(),
     ]
     .into_iter()
     .flatten()
     .collect()
 }
 
-pub fn get_server_json(hostname: &str, port: u16) -> Result<String, Box<dyn error::Error>> {
-    let socket_addr = match format!("{}:{}", hostname, port).to_socket_addrs()?.next() {
-        Some(socket) => socket,
-        None => return Err("Failed to parse hostname".into()),
-    };
+fn check_frame_length(
+    frame_length: i32,
+    prefix_length: u32,
+    string_length: i32,
+) -> Result<(), QueryError> {
+    // The outer length covers the packet id, the string length prefix and the string itself. The
+    // prefix length is what was actually read, so over-long VarInts don't count as a mismatch.
+    let expected = prefix_length as i64 + string_length as i64;
+    if frame_length as i64 != expected {
+        return Err(QueryError::FrameLengthMismatch {
+            frame_length: frame_length as i64,
+            expected,
+        });
+    }
+    Ok(())
+}
+
+fn check_string_length(
+    string_length: i32,
+    max_packet_size: Option<u32>,
+) -> Result<u32, Box<dyn error::Error + Send + Sync>> {
+    // A crafted negative VarInt would wrap into a huge allocation, so it's rejected even without a limit
+    let string_length = u32::try_from(string_length).map_err(|_| QueryError::InvalidLength {
+        length: string_length as i64,
+    })?;
+    if max_packet_size.is_some_and(|max| string_length > max) {
+        return Err("Response too large".into());
+    }
+    Ok(string_length)
+}
+
+fn check_not_minecraft(sniffed: [u8; 2]) -> Result<(), QueryError> {
+    // A status response starts with its length and packet id 0x00, so "HT" (an HTTP response) or
+    // 0x16 0x03 (a TLS handshake record) means something else is listening on the port.
+    match sniffed {
+        [b'H', b'T'] => Err(QueryError::NotAMinecraftServer { protocol: "HTTP" }),
+        [0x16, 0x03] => Err(QueryError::NotAMinecraftServer { protocol: "TLS" }),
+        _ => Ok(()),
+    }
+}
 
-    let mut stream = TcpStream::connect_timeout(&socket_addr, TIMEOUT)?; // Connect to socket
+fn closed_early(error: Box<dyn error::Error + Send + Sync>) -> Box<dyn error::Error + Send + Sync> {
+    // EOF before the length and packet id are in means the server hung up on us (full, anti-bot)
+    match error.downcast_ref::<io::Error>() {
+        Some(io_error) if io_error.kind() == io::ErrorKind::UnexpectedEof => {
+            QueryError::ConnectionClosedEarly.into()
+        }
+        _ => error,
+    }
+}
 
-    stream.write_all(&status_packet_builder(hostname, port))?; // Send status request
+fn sniff_response(
+    stream: &mut impl Read,
+) -> Result<impl Read + '_, Box<dyn error::Error + Send + Sync>> {
+    // Returns a reader that replays the sniffed bytes before the rest of the stream
+    let mut sniffed = [0; 2];
+    stream.read_exact(&mut sniffed)?;
+    check_not_minecraft(sniffed)?;
+    Ok(io::Cursor::new(sniffed).chain(stream))
+}
 
-    let _length = var_int_read(&mut stream)?; // Unpack length from status response (unused)
-    let _id = var_int_read(&mut stream)?; // Unpack id from status response (unused)
-    let string_length = var_int_read(&mut stream)?; // Unpack string length from reponse
+struct StatusHeader {
+    frame_length: i32,
+    packet_id: i32,
+    string_length: u32,
+}
 
-    if string_length as u32 > MAX_PACKET_SIZE {
-        return Err("Response too large".into());
+impl StatusHeader {
+    fn parse(
+        frame_length: i32,
+        (packet_id, id_length): (i32, u32),
+        (string_length, prefix_length): (i32, u32),
+        max_packet_size: Option<u32>,
+    ) -> Result<StatusHeader, Box<dyn error::Error + Send + Sync>> {
+        // Takes the three header VarInts with their encoded sizes. Both the blocking and the async
+        // reader go through here, so they reject the same responses with the same errors.
+        let checked_length = check_string_length(string_length, max_packet_size)?; // Capped
+        if checked_length == 0 {
+            return Err(QueryError::EmptyResponse.into()); // Otherwise surfaces as a confusing JSON EOF error
+        }
+        check_frame_length(frame_length, id_length + prefix_length, string_length)?;
+        Ok(StatusHeader {
+            frame_length,
+            packet_id,
+            string_length: checked_length,
+        })
     }
+}
+
+fn read_status_header(
+    stream: &mut impl Read,
+    max_packet_size: Option<u32>,
+) -> Result<StatusHeader, Box<dyn error::Error + Send + Sync>> {
+    let sniffed = sniff_response(stream).map_err(closed_early)?;
+    let mut reader = FrameReader::new(sniffed, max_packet_size);
+    let frame_length = reader.read_varint().map_err(closed_early)?; // Unpack length from status response
+    let packet_id = reader.read_varint_sized().map_err(closed_early)?; // Unpack id from status response
+    let string_length = reader.read_varint_sized()?; // Unpack string length from reponse
+    StatusHeader::parse(frame_length, packet_id, string_length, max_packet_size)
+}
+
+fn read_status_response(
+    stream: &mut impl Read,
+    max_packet_size: Option<u32>,
+) -> Result<Vec<u8>, Box<dyn error::Error + Send + Sync>> {
+    let string_length = read_status_header(stream, max_packet_size)?.string_length;
 
     let mut buffer = vec![0; string_length as usize]; // Make buffer the size of the string
 
     stream.read_exact(&mut buffer)?; // Read into buffer
+    Ok(buffer)
+}
 
-    let json: serde_json::Value = serde_json::from_str(&String::from_utf8(buffer)?)?;
-    Ok(json.to_string())
+fn read_payload(
+    stream: &mut impl Read,
+    buffer: &mut Vec<u8>,
+    string_length: u32,
+    max_read_calls: Option<usize>,
+) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+    // Reads the announced payload into buffer, within the read call cap
+    let mut payload = CountingReader::new(stream);
+    payload.limit_reads(max_read_calls);
+    buffer.clear();
+    buffer.resize(string_length as usize, 0);
+    let result = payload.read_exact(buffer).map_err(Into::into);
+    payload.limited(result)
 }
 
-fn parse_json(json: &str) -> Result<ServerStatus, Box<dyn error::Error>> {
-    Ok(serde_json::from_str(json)?)
+fn ping_packet_builder(payload: i64) -> Vec<u8> {
+    // Ping is packet id 0x01 followed by a long the server echoes back
+    var_int_pack([vec![0x01], payload.to_be_bytes().to_vec()].concat())
+}
+
+fn check_pong_header(length: i32, id: i32) -> Result<(), QueryError> {
+    if length != 9 || id != 0x01 {
+        return Err(QueryError::InvalidResponse {
+            reason: format!(
+                "expected a pong, got packet {:#04x} of length {}",
+                id, length
+            ),
+        });
+    }
+    Ok(())
+}
+
+fn check_pong_payload(echoed: [u8; 8], payload: i64) -> Result<(), QueryError> {
+    if i64::from_be_bytes(echoed) != payload {
+        return Err(QueryError::InvalidResponse {
+            reason: "pong payload doesn't match the ping".into(),
+        });
+    }
+    Ok(())
+}
+
+fn read_pong(
+    stream: &mut impl Read,
+    payload: i64,
+) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+    // Reads the whole pong so nothing is left behind on the stream
+    let mut reader = FrameReader::new(&mut *stream, None); // check_pong_header checks the length
+    let length = reader.read_varint()?;
+    let id = reader.read_varint()?;
+    check_pong_header(length, id)?;
+
+    let mut echoed = [0; 8];
+    stream.read_exact(&mut echoed)?;
+    check_pong_payload(echoed, payload)?;
+    Ok(())
+}
+
+pub fn send_handshake_and_status(
+    stream: &mut TcpStream,
+    handshake_bytes: &[u8],
+) -> Result<String, Box<dyn error::Error + Send + Sync>> {
+    // Sends caller supplied handshake bytes as-is (useful for fuzzing), then a regular status request.
+    // Returns the raw response string without any normalization.
+    stream.write_all(handshake_bytes)?;
+    stream.write_all(&status_request_builder())?;
+    Ok(String::from_utf8(read_status_response(
+        stream,
+        Some(MAX_PACKET_SIZE),
+    )?)?)
+}
+
+fn validate_address(hostname: &str, port: u16) -> Result<String, QueryError> {
+    // Catches obvious misuse before any network I/O, and normalizes the hostname we connect and handshake with.
+    let hostname = hostname.trim().to_lowercase();
+
+    if hostname.is_empty() {
+        return Err(QueryError::InvalidAddress {
+            reason: "hostname is empty".into(),
+        });
+    }
+    if hostname.chars().any(char::is_whitespace) {
+        return Err(QueryError::InvalidAddress {
+            reason: "hostname contains whitespace".into(),
+        });
+    }
+    if port == 0 {
+        return Err(QueryError::InvalidAddress {
+            reason: "port is 0".into(),
+        });
+    }
+
+    // Anything with a colon must be an IPv6 literal, optionally already wrapped in brackets
+    if hostname.contains(':') {
+        let literal = hostname
+            .strip_prefix('[')
+            .and_then(|literal| literal.strip_suffix(']'))
+            .unwrap_or(&hostname);
+        return match literal.parse::<Ipv6Addr>() {
+            Ok(_) => Ok(literal.to_string()),
+            Err(_) => Err(QueryError::InvalidAddress {
+                reason: format!("malformed IPv6 literal \"{}\"", hostname),
+            }),
+        };
+    }
+
+    Ok(hostname)
+}
+
+pub fn resolve(hostname: &str, port: u16) -> Result<Vec<SocketAddr>, QueryError> {
+    // Every address the hostname resolves to, in the order queries try them
+    let hostname = validate_address(hostname, port)?;
+    let address = if hostname.contains(':') {
+        format!("[{}]:{}", hostname, port) // IPv6 literals need brackets to be parsed with a port
+    } else {
+        format!("{}:{}", hostname, port)
+    };
+
+    Ok(address.to_socket_addrs()?.collect())
+}
+
+fn connect(
+    socket_addrs: &[SocketAddr],
+    timeout: Duration,
+    options: &SocketOptions,
+) -> Result<TcpStream, Box<dyn error::Error + Send + Sync>> {
+    // Tries every resolved address in order, each with its own timeout, a hostname may resolve to an
+    // unreachable IPv6 before a working IPv4.
+    if socket_addrs.is_empty() {
+        return Err("Failed to parse hostname".into());
+    }
+    let mut errors = vec![];
+
+    for socket_addr in socket_addrs {
+        match options.connect(socket_addr, timeout) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(timeout))?; // A slow server shouldn't hang the read forever
+                return Ok(stream);
+            }
+            Err(error) => {
+                if let (Some(port), io::ErrorKind::AddrInUse) = (options.source_port, error.kind())
+                {
+                    return Err(QueryError::SourcePortInUse { port }.into()); // Same for every address
+                }
+                errors.push(error);
+            }
+        }
+    }
+
+    Err(QueryError::connect_failed(errors).into())
+}
+
+fn timed_server_json(
+    query: &StatusQuery,
+    timings: &mut Timings,
+    transcript: &mut Option<Transcript>,
+) -> Result<String, Box<dyn error::Error + Send + Sync>> {
+    Ok(timed_server_value(query, timings, transcript)?.to_string())
+}
+
+fn timed_server_value(
+    query: &StatusQuery,
+    timings: &mut Timings,
+    transcript: &mut Option<Transcript>,
+) -> Result<serde_json::Value, Box<dyn error::Error + Send + Sync>> {
+    let mut json = timed_status(query, timings, transcript)?;
+
+    if let Some(redirect) = &query.redirect {
+        // The callback decides from each status whether the real server is elsewhere, we only guard against loops
+        let mut redirects = 0;
+        while let Some((hostname, port)) = (redirect.callback)(&json) {
+            if redirects == redirect.max_redirects {
+                return Err("Too many redirects".into());
+            }
+            redirects += 1;
+            json = timed_status(&query.redirected(&hostname, port), timings, transcript)?;
+        }
+    }
+
+    Ok(json)
+}
+
+fn open_stream(
+    query: &StatusQuery,
+    hostname: &str,
+    timings: &mut Timings,
+) -> Result<TcpStream, Box<dyn error::Error + Send + Sync>> {
+    match &query.proxy {
+        // The proxy resolves the hostname, so resolve is left at zero
+        Some(proxy) => timed(&*query.clock, &mut timings.connect, || {
+            proxy::connect_via(
+                proxy,
+                hostname,
+                query.port,
+                query.timeout,
+                &query.socket_options,
+            )
+        }),
+        None => {
+            let socket_addrs = match &query.socket_addrs {
+                Some(socket_addrs) => socket_addrs.clone(), // Already resolved, e.g. from the scanner's DNS cache
+                None => timed(&*query.clock, &mut timings.resolve, || {
+                    resolve(hostname, query.port)
+                })?,
+            };
+
+            timed(&*query.clock, &mut timings.connect, || {
+                connect(&socket_addrs, query.timeout, &query.socket_options)
+            }) // Connect to the first reachable address
+        }
+    }
+}
+
+fn timed_server_status(
+    query: &StatusQuery,
+    timings: &mut Timings,
+    transcript: &mut Option<Transcript>,
+) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+    // Deserializes the payload straight into a ServerStatus. Redirect callbacks need the JSON
+    // value, so only then is it built first.
+    with_sample_cap(query.max_sample_players, || {
+        if query.redirect.is_some() {
+            let json = timed_server_value(query, timings, transcript)?;
+            return timed(&*query.clock, &mut timings.parse, || {
+                Ok(serde_json::from_value(json)?)
+            });
+        }
+        timed_status(query, timings, transcript)
+    })
+}
+
+fn timed_status<T: DeserializeOwned>(
+    query: &StatusQuery,
+    timings: &mut Timings,
+    transcript: &mut Option<Transcript>,
+) -> Result<T, Box<dyn error::Error + Send + Sync>> {
+    let hostname = validate_address(&query.hostname, query.port)?;
+
+    let stream = open_stream(query, &hostname, timings)?;
+    let mut stream = Recorder::new(stream, query.capture);
+
+    timings.bytes_sent += timed(&*query.clock, &mut timings.write, || {
+        query.send_status_request(&mut stream, &hostname)
+    })?; // Send status request
+
+    let mut counted = CountingReader::new(&mut stream);
+    let response = timed(&*query.clock, &mut timings.read, || {
+        let header = read_status_header(&mut counted, query.max_packet_size)?;
+        counted.limit_reads(query.max_read_calls);
+        let payload = match query.json_end {
+            Some(trailing) => Payload::Parsed(read_json_value(
+                &mut counted,
+                header.string_length,
+                trailing,
+            )?),
+            None => {
+                let mut buffer = vec![0; header.string_length as usize];
+                counted.read_exact(&mut buffer)?;
+                Payload::Bytes(buffer)
+            }
+        };
+        Ok::<_, Box<dyn error::Error + Send + Sync>>((header.frame_length, payload))
+    });
+    let response = counted.limited(response);
+    timings.bytes_received += counted.count; // Counted even when the read failed part way
+    *transcript = stream.transcript; // After a redirect only the last server's exchange is kept
+    let (frame_length, payload) = response?;
+    timings.frame_length = frame_length as usize;
+
+    match payload {
+        Payload::Parsed(value) => Ok(value), // Already parsed while reading
+        Payload::Bytes(buffer) => timed(&*query.clock, &mut timings.parse, || {
+            Ok(serde_json::from_str(&query.decode(buffer)?)?)
+        }),
+    }
+}
+
+enum Payload<T> {
+    Bytes(Vec<u8>),
+    Parsed(T),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailingData {
+    Ignore,
+    Reject,
+}
+
+fn read_json_value<T: DeserializeOwned>(
+    stream: &mut impl Read,
+    string_length: u32,
+    trailing: TrailingData,
+) -> Result<T, Box<dyn error::Error + Send + Sync>> {
+    // Parses straight off the socket and stops after the first complete value, so padding a
+    // lying length with junk costs the server its own bandwidth, not our memory. The BufReader
+    // keeps serde_json from making a read call per byte, it reads at most one buffer past the value.
+    let mut deserializer =
+        serde_json::Deserializer::from_reader(BufReader::new(stream.take(string_length as u64)));
+    let value = T::deserialize(&mut deserializer).map_err(json_read_error)?;
+    if trailing == TrailingData::Reject {
+        deserializer.end()?; // Only whitespace may follow
+    }
+    Ok(value)
+}
+
+fn json_read_error(error: serde_json::Error) -> Box<dyn error::Error + Send + Sync> {
+    // Socket failures (timeouts, early EOF) stay io::Errors so they classify like any other read,
+    // everything else keeps the typed serde_json::Error
+    if error.is_io() {
+        io::Error::from(error).into()
+    } else {
+        error.into()
+    }
+}
+
+fn query_ping(query: &StatusQuery) -> Result<Duration, Box<dyn error::Error + Send + Sync>> {
+    let hostname = validate_address(&query.hostname, query.port)?;
+    let mut stream = open_stream(query, &hostname, &mut Timings::default())?;
+
+    query.send_status_request(&mut stream, &hostname)?; // Send status request
+    let string_length = read_status_header(&mut stream, query.max_packet_size)?.string_length;
+    read_payload(
+        &mut stream,
+        &mut vec![],
+        string_length,
+        query.max_read_calls,
+    )?; // Servers only answer a ping after the status exchange
+
+    let payload = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64;
+    let start = query.clock.now();
+    stream.write_all(&ping_packet_builder(payload))?;
+    read_pong(&mut stream, payload)?;
+    Ok(query.clock.now() - start)
+}
+
+pub fn ping(
+    hostname: &str,
+    port: impl Into<StatusPort>,
+) -> Result<Duration, Box<dyn error::Error + Send + Sync>> {
+    StatusQuery::new(hostname, port).ping()
+}
+
+fn query_probe(query: &StatusQuery) -> Result<(), QueryError> {
+    // Cheap liveness check, stops after the packet id so the payload (and any huge favicon) is never read.
+    let hostname = validate_address(&query.hostname, query.port)?;
+    let mut stream =
+        open_stream(query, &hostname, &mut Timings::default()).map_err(QueryError::from_boxed)?;
+    query.send_status_request(&mut stream, &hostname)?; // Send status request
+
+    let stream =
+        sniff_response(&mut stream).map_err(|error| QueryError::from_boxed(closed_early(error)))?;
+    let mut reader = FrameReader::new(stream, query.max_packet_size);
+    let _length = reader
+        .read_varint()
+        .map_err(|error| QueryError::from_boxed(closed_early(error)))?; // Unpack length from status response (unused)
+    let id = reader
+        .read_varint()
+        .map_err(|error| QueryError::from_boxed(closed_early(error)))?;
+    if id != 0x00 {
+        return Err(QueryError::InvalidResponse {
+            reason: format!("expected status packet id 0x00, got {:#04x}", id),
+        });
+    }
+    Ok(())
+}
+
+pub fn probe(
+    hostname: &str,
+    port: impl Into<StatusPort>,
+    timeout: Duration,
+) -> Result<(), QueryError> {
+    StatusQuery::new(hostname, port).timeout(timeout).probe()
+}
+
+fn connect_time(query: &StatusQuery) -> Result<Duration, QueryError> {
+    // Lightest probe there is, no handshake at all. Only the successful attempt is timed.
+    let hostname = validate_address(&query.hostname, query.port)?;
+    if query.proxy.is_some() {
+        // Through a proxy the whole tunnel setup is timed, the proxy picks the address
+        let mut timings = Timings::default();
+        open_stream(query, &hostname, &mut timings).map_err(QueryError::from_boxed)?;
+        return Ok(timings.connect);
+    }
+    let socket_addrs = match &query.socket_addrs {
+        Some(socket_addrs) => socket_addrs.clone(),
+        None => resolve(&hostname, query.port)?,
+    };
+    let mut errors = vec![];
+
+    for socket_addr in &socket_addrs {
+        let start = query.clock.now();
+        match query.socket_options.connect(socket_addr, query.timeout) {
+            Ok(_) => return Ok(query.clock.now() - start), // Dropped straight away
+            Err(error) => errors.push(error),
+        }
+    }
+    Err(QueryError::connect_failed(errors))
+}
+
+pub fn tcp_connect_time(
+    hostname: &str,
+    port: impl Into<StatusPort>,
+    timeout: Duration,
+) -> Result<Duration, QueryError> {
+    StatusQuery::new(hostname, port)
+        .timeout(timeout)
+        .tcp_connect_time()
+}
+
+pub fn get_server_json(
+    hostname: &str,
+    port: impl Into<StatusPort>,
+) -> Result<String, Box<dyn error::Error + Send + Sync>> {
+    StatusQuery::new(hostname, port).get_server_json()
+}
+
+pub fn get_server_json_pretty(
+    hostname: &str,
+    port: impl Into<StatusPort>,
+) -> Result<String, Box<dyn error::Error + Send + Sync>> {
+    StatusQuery::new(hostname, port).get_server_json_pretty()
+}
+
+pub fn parse_json(json: &str) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
     // Cast json to our custom object "ServerResponse"
+    // Accepts everything the network path does, so anything get_server_json returned parses back without error.
+    Ok(serde_json::from_str(json)?)
+}
+
+pub fn parse_status_frame(
+    bytes: &[u8],
+) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+    // Offline counterpart of server_status, for replaying a captured response (framing included)
+    let buffer = read_status_response(&mut &bytes[..], Some(MAX_PACKET_SIZE))?;
+    parse_json(&String::from_utf8(buffer)?)
+}
+
+pub fn server_status(
+    hostname: &str,
+    port: impl Into<StatusPort>,
+) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+    StatusQuery::new(hostname, port).server_status()
+}
+
+pub fn server_status_default_port(
+    hostname: &str,
+) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+    server_status(hostname, DEFAULT_PORT)
+}
+
+fn split_address(address: &str) -> Result<(&str, u16), QueryError> {
+    // Splits "host:port" on the last colon, "[::1]:25565" keeps its brackets for validate_address to strip.
+    // A bare IPv6 literal like "::1" has no port, and neither does a plain "host".
+    let address = address.trim();
+    let port_colon = match address.rfind(':') {
+        Some(index) if address.starts_with('[') => address[..index].ends_with(']').then_some(index),
+        Some(index) if address.matches(':').count() == 1 => Some(index),
+        _ => None,
+    };
+
+    match port_colon {
+        Some(index) => {
+            let port = address[index + 1..]
+                .parse()
+                .map_err(|_| QueryError::InvalidAddress {
+                    reason: format!("invalid port in \"{}\"", address),
+                })?;
+            Ok((&address[..index], port))
+        }
+        None => Ok((address, DEFAULT_PORT)),
+    }
+}
+
+pub fn server_status_str(
+    address: &str,
+) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+    // Accepts the "host:port" form copied from a server list, the port defaults to 25565.
+    let (hostname, port) = split_address(address)?;
+    server_status(hostname, port)
+}
+
+fn streaming_status(
+    query: &StatusQuery,
+) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+    // Deserializes straight from the socket instead of buffering the payload, keeps peak memory down for huge modded responses.
+    // The take limit still bounds the read to the announced (and capped) string length.
+    let hostname = validate_address(&query.hostname, query.port)?;
+    let mut stream = open_stream(query, &hostname, &mut Timings::default())?;
+    query.send_status_request(&mut stream, &hostname)?; // Send status request
+
+    let string_length = read_status_header(&mut stream, query.max_packet_size)?.string_length;
+    if query.lossy_utf8 && query.json_end.is_none() {
+        // Replacing invalid UTF-8 needs the whole payload, so this falls back to buffering it
+        let mut buffer = vec![];
+        read_payload(
+            &mut stream,
+            &mut buffer,
+            string_length,
+            query.max_read_calls,
+        )?;
+        let json = query.decode(buffer)?;
+        return with_sample_cap(query.max_sample_players, || parse_json(&json));
+    }
+    let mut payload = CountingReader::new(&mut stream);
+    payload.limit_reads(query.max_read_calls);
+    let result = with_sample_cap(query.max_sample_players, || match query.json_end {
+        Some(trailing) => read_json_value(&mut payload, string_length, trailing),
+        None => {
+            let reader = BufReader::new((&mut payload).take(string_length as u64)); // Not a read call per byte
+            serde_json::from_reader(reader).map_err(json_read_error)
+        }
+    });
+    payload.limited(result)
+}
+
+fn buffered_status(
+    query: &StatusQuery,
+    buffer: &mut Vec<u8>,
+) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+    // Keeps the caller's allocation, so tight scanning loops don't allocate a payload buffer per query.
+    // Redirects aren't followed on this path.
+    let hostname = validate_address(&query.hostname, query.port)?;
+    let mut stream = open_stream(query, &hostname, &mut Timings::default())?;
+    query.send_status_request(&mut stream, &hostname)?; // Send status request
+
+    let string_length = read_status_header(&mut stream, query.max_packet_size)?.string_length;
+    if let Some(trailing) = query.json_end {
+        // Parsed straight off the socket, so the buffer is left empty
+        buffer.clear();
+        let mut payload = CountingReader::new(&mut stream);
+        payload.limit_reads(query.max_read_calls);
+        let result = with_sample_cap(query.max_sample_players, || {
+            read_json_value(&mut payload, string_length, trailing)
+        });
+        return payload.limited(result);
+    }
+    read_payload(&mut stream, buffer, string_length, query.max_read_calls)?; // Read into buffer
+
+    with_sample_cap(query.max_sample_players, || {
+        if query.lossy_utf8 {
+            return parse_json(&String::from_utf8_lossy(buffer));
+        }
+        Ok(serde_json::from_slice(buffer)?)
+    })
+}
+
+pub fn server_status_into(
+    hostname: &str,
+    port: impl Into<StatusPort>,
+    buffer: &mut Vec<u8>,
+) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+    StatusQuery::new(hostname, port).server_status_into(buffer)
+}
+
+pub fn server_status_streaming(
+    hostname: &str,
+    port: impl Into<StatusPort>,
+) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+    StatusQuery::new(hostname, port).server_status_streaming()
+}
+
+pub fn early_status(
+    hostname: &str,
+    port: impl Into<StatusPort>,
+) -> Result<EarlyStatus, Box<dyn error::Error + Send + Sync>> {
+    StatusQuery::new(hostname, port).early_status()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawStatusFrame {
+    pub frame_length: i32,
+    pub packet_id: i32,
+    pub json: String,
+}
+
+fn raw_frame(query: &StatusQuery) -> Result<RawStatusFrame, Box<dyn error::Error + Send + Sync>> {
+    // The status response exactly as framed on the wire, nothing normalized or parsed.
+    // The frame length has already been checked against the string length by read_status_header.
+    if query.json_end.is_some() {
+        // Cutting the payload at the end of the JSON would no longer be the frame as sent
+        return Err("stop_at_json_end doesn't apply to raw status frames".into());
+    }
+    let hostname = validate_address(&query.hostname, query.port)?;
+    let mut stream = open_stream(query, &hostname, &mut Timings::default())?;
+    query.send_status_request(&mut stream, &hostname)?; // Send status request
+
+    let header = read_status_header(&mut stream, query.max_packet_size)?;
+    let mut buffer = vec![];
+    read_payload(
+        &mut stream,
+        &mut buffer,
+        header.string_length,
+        query.max_read_calls,
+    )?;
+    Ok(RawStatusFrame {
+        frame_length: header.frame_length,
+        packet_id: header.packet_id,
+        json: query.decode(buffer)?,
+    })
+}
+
+pub fn raw_status_frame(
+    hostname: &str,
+    port: impl Into<StatusPort>,
+) -> Result<RawStatusFrame, Box<dyn error::Error + Send + Sync>> {
+    StatusQuery::new(hostname, port).raw_status_frame()
+}
+
+pub struct FullStatus {
+    pub status: ServerStatus,
+    pub timings: Timings,
+    pub transcript: Option<Transcript>, // Only with StatusQuery::capture
+}
+
+fn full_query_status(
+    query: &StatusQuery,
+) -> Result<FullStatus, Box<dyn error::Error + Send + Sync>> {
+    // Same as server_status, but also reports how long each phase of the query took
+    let mut timings = Timings::default();
+    let mut transcript = None;
+    let status = timed_server_status(query, &mut timings, &mut transcript)?;
+    Ok(FullStatus {
+        status,
+        timings,
+        transcript,
+    })
 }
 
-pub fn server_status(hostname: &str, port: u16) -> Result<ServerStatus, Box<dyn error::Error>> {
-    let raw_json = get_server_json(hostname, port)?;
-    parse_json(&raw_json)
+pub fn full_status(
+    hostname: &str,
+    port: impl Into<StatusPort>,
+) -> Result<FullStatus, Box<dyn error::Error + Send + Sync>> {
+    StatusQuery::new(hostname, port).full_status()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    fn status_response(json: &str) -> Vec<u8> {
+        // Frames json the way a server would, packet length, packet id 0x00, then the string
+        var_int_pack([vec![0x00], var_int_pack(json.as_bytes().to_vec())].concat())
+    }
+
+    fn status_json(motd: &str) -> String {
+        format!("{{\"version\":{{\"protocol\":758,\"name\":\"1.18.2\"}},\"players\":{{\"online\":3,\"max\":20}},\"description\":{{\"text\":\"{}\"}}}}", motd)
+    }
+
+    fn default_status_server() -> u16 {
+        // Answers a single query with a minimal valid status
+        mock_server(status_response(&status_json("")), Duration::ZERO)
+    }
+
+    fn earthmc_status() -> ServerStatus {
+        // The parse test's response without its favicon
+        parse_json("{\"version\":{\"protocol\":758,\"name\":\"Velocity 1.7.2-1.18.2\"},\"players\":{\"online\":196,\"max\":150,\"sample\":[]},\"description\":{\"extra\":[{\"bold\":true,\"extra\":[{\"color\":\"aqua\",\"text\":\"E\"},{\"color\":\"aqua\",\"text\":\"a\"},{\"color\":\"aqua\",\"text\":\"r\"},{\"color\":\"aqua\",\"text\":\"t\"},{\"color\":\"aqua\",\"text\":\"h\"},{\"color\":\"green\",\"text\":\"M\"},{\"color\":\"green\",\"text\":\"C\"}],\"text\":\"\"},{\"text\":\"\\n\"},{\"color\":\"gray\",\"text\":\"Slava Ukraini!\"}],\"text\":\"\"}}").unwrap()
+    }
+
+    fn mock_server(response: Vec<u8>, delay: Duration) -> u16 {
+        // Serves a single connection, writing the response one byte at a time when a delay is given
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.set_nodelay(true).unwrap();
+            let _ = stream.read(&mut [0; 1024]); // Handshake and status request
+            if delay.is_zero() {
+                let _ = stream.write_all(&response); // The client may hang up early
+            } else {
+                for byte in response {
+                    stream.write_all(&[byte]).unwrap();
+                    thread::sleep(delay);
+                }
+            }
+        });
+        port
+    }
+
     #[test]
     fn parse() {
         let server_response = parse_json("{\"version\":{\"protocol\":758,\"name\":\"Velocity 1.7.2-1.18.2\"},\"players\":{\"online\":196,\"max\":150,\"sample\":[]},\"description\":{\"extra\":[{\"bold\":true,\"extra\":[{\"color\":\"aqua\",\"text\":\"E\"},{\"color\":\"aqua\",\"text\":\"a\"},{\"color\":\"aqua\",\"text\":\"r\"},{\"color\":\"aqua\",\"text\":\"t\"},{\"color\":\"aqua\",\"text\":\"h\"},{\"color\":\"green\",\"text\":\"M\"},{\"color\":\"green\",\"text\":\"C\"}],\"text\":\"\"},{\"text\":\"\\n\"},{\"color\":\"gray\",\"text\":\"Slava Ukraini!\"}],\"text\":\"\"},\"favicon\":\"data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAEAAAABACAIAAAAlC+aJAAAYrklEQVR4Xu2ad1RbV56Atbsze2Z39mTSZk52JzMptuPEha4KqAsJRJFEE6AKqIKopmNMM9UG47jFLS4SvQiwsY1tJIoq4BInM7PJZnZqJo4dtxjHY7DZe98TQpR4nM3M7D/7znfueUhXv/v73r3vvvd4F8Pf9RZA0LI+9SRR3Rasag0CKA1kbZd/TE0+VmgjSi4QxCMoeMlF7+h2b77em2+ACE5ujuglZ2r4e38ateutqJa1KLzd61L1IBRN2UoFKAx0bTc2srIyQDhJkJjx4jGEcZxoxFvQ4c1vc+PFM/glDOJlZrxkZBHRRR9B27JG6flS/p7XI5s3YL6LgA+IFY4I7Ptp5BKBt1INgcpWiltAAwUqVhNoXymAWyYgvugDv/07CvC/swDubyyABFoU0PL3vhq5a31UyzrILtAD61MNQUj2YBTRFHqGpgv3DUPIU6AdCiQO4uQm0JALKRC44DmEQKNekS6BKFSA17wOlM8s0ObNQ2PpvQUnNkEBtWD/K1Eta6J2v8Hb/UbU7tf5e9Yo2giq9iAVCNgeDLpC2+MXWVWOCJiQ7EdBuSDQimQPSy+e3ld4CisZx4rMONEoRDyKSzJ78zq9olq9otogPMMmbj8tT8bf81pk0wZM9O63ATHvvqPQkxABBAMlrSsgpjYfl+ggykxE6SgKXjLqE9vlG93mG90OiQFBjeSMXN4u74hGYsQOPKQRH7WTKD/KST4WiiJ/P0yhp4VvqwtIcsITFMQByMZwEpNvdJevoNM32oUPvwMn7iOrTpEVgMFgxSDYCU4dwCUcxSUcQcEnHsLG6kOKk6L3vclv2YRRgsGKoG4ja9opKOo2WnoPLqahGCe+SkqxkpLtCA5iso0gNhOSRt3gRWaSzBSUal4GMeUcQX7WDTH5LDHFREyxEVOsSImQbCWIzASRCYUoHsHG26JrKjL6mJr2SG1HuBulgaPUe8JW6qlKfZBSH4xBxwzAnb1bILqxBCf+IDDFBlJfwI6XjBFEbsZBSZQCwymSfNINUT4JhkGAeMwTgnyCBEJ5kmzzjEaUmAPinbF1W3NOETJ6GJkLgH1tJ1XTQXGj7QQZUlQGMuA7CgDA0LKQUuBXnuCkZqzEhJWgJYQgH19FQOwhIDYHxDlj6rblDBB1XayMbkAIAOxr2umadpoHdHUr9a8qsFjBBSKwhGcWKMsZJOq6mYgARNfNWpo9RN36DD0Q01iMl14JSrUAhwWsBKmZKBklit2Ac2BiNYHRZxCwEjxCkaQmrNARu0IgY1WBtpU90OYh0AoEsPy6Mt/4j3GSy1jxVReiKz5Cu2+8zS/O5huHlLE2nMgemOIkJaM40BIrNQeITZ4AgcBU+2L2qeAktvkJ7X7xLvyFVi/+VcH2ipxBgq4LCLgcwP4qAu4e0LSR1QhpnbT0rgU66bqeINVRqXxHQ3JTtbxpO2Tn9uSm7aktNcrdtcoWiGJXnWZvFa9YjxU7QH542QRBbkEhJk94QkqBn4A6bvAyS1DqiLi+LrmxUt5YLW+sAjuyujr10eTsfmpWb0hWL9tNeicjrZORjoDuaJFOAGDcRz0DOd9RwLmv62EUGUPqhsJqTnMhQ7CsHeI2j0TvMsfuMsWCsnkkfq81QtFS45NgwckuYKUjKDiZKVhpJ6scKOg+EAD9sDCiTP6icYpyqHxAUHsmrGYoHKVuKLxkMDTbGJprDM3xwHM4ATK7WcAB7YpFAV033dNB10PP7+FW9MWWL6XujLj+rKT+jASUdUPSnRfj5E2NvonLBMwg6WWsFCArzhR3J2zr55f1RZcZBYBtxujCvvBsI8cz++w+zjIBQNozCfSGVRqjK5ZSfzap4ZwIBTg0m2KTgcBqPeDJqj0ABEp64ssHorb1C4AGoLyfX2T81gJUlIzuxey/WSCm/ixIHXSCuP6cuO6MpGlkVYHlPbBSIAAV6BaWD/Dgse8XlPULyvsFhcaI7D6Qd1hOH4IxDPyZ0QUvCJ6kdTDVbeDiQMdoOwNRQMY6ZOjrEIF0OISgQPlSh8Zh4c7zCTsQGoeTWkb5Kc31nudAgPQiTmoKUngIwP3lAkgPDG3ti6k8FV4+EFkxGFE+GFE5GFk8wMkZYG0ZYOUioDuZvbRl6LrB9TgYgFEc46ce4yuO8zK7wYnPyupxkdnDLOzlVvXHVC4httKoqjRqUCr6tDWnU6QN7/on2fByM7xRk43CxxH5KFltJ6sdbigaByl1AqQOrg8o4F6DojyzpVVV1CUt7JSjFHXKc9rFaa0J6a2JnmgNsVpDzCL6OOWJ2JRj0QAMQz3OUFlZWnNhm6xyIKLCyK8w8gDlRl716djGYddwB8MGHO/aoZSorRdD8qc4BXZOgYOd7+AUOphZdorKRlHbKSBRBKraHrLFGZLvDMlzlez8SUaGk6xarENR2alqGzvfBkKxCxwciB2JZqGrJ+iaRRhpE+wCGxtWg4Qg9RmZVqp6gqaxYOhKO00xyVDZitrkVQNR5X2CciMfsK2PX30qtmFYVH8uCQKG/nBizZCcWzzKyLnC2jLF3DLN2jIdkjdNy5gMVtpAcq5JUwXzY6Gpuylw0mE118SKQoXVJkGEECQUM3eamTdNTXeQU51k5SRZ4SQrkFLlpG+Zpue5YORNMwsu0XWTFIWTqnICAStd6WCqLYgAr9oYv70fUm2Mqz+d1HRetnNYunMYlLKm85KGMyn8souhBVPcIge30MHOc7LyJ0NybAythZlmZabZEKx0jY21xeHOHmQZWjjJybEztLAOA6kJ6lBVdqSLgMNUWKETBOQWOdnZNpbWwkqzutBaWTpLWJEjrMiJAJwdzIIpeoaTqrRTVQ4gYKMrnUy1taBVWnsq7vc3/vPegxu37//pzv3P78x8fnfmuid3Zq5/fuurz798ALh++37Kzo+Yec5rv7554/bXf7wx89nNmT98MXN35sG7Xb+mZF/iFk+y853c4ilylv3k8G/uzTz8/Rf3QR1Q3pn5uvvC7wjJUCysEHSj0/oLEPjBH7+8/xng5nL+dAt+/sWdB59+diem8gNG/jQ9w05R2pYIFLZJ604J783cnp+ff/LkyfwzbDHZR0nKnpt3H84v/GR27jEoG/YNvcLYTc52MLY4gjJtG+RjXabfgs/nHsM6c0idwQtX1oXsYeXZiel2rHriV7/9Enz4+JvbRePfuz9Dk+8n64aZ2UDAAvoQQ1dYaakOpsqSrxfXDMTevY8GAqG+cUO+g0ED6TFegoYbX8GEZh8/mXsy/whJ7sLIWO2ejgOnfhNfPX3ozKcHT3/64X/fmkcEwPZodhbst7X3/PN/cBKrrxjO/+bI6U+v356B7T5+jIRfhceP50CF27dvrd3M9hd10rOvUhQTYBRhGFk2epY9JNtSM6Q7MKH66us7UHd+8UiAJmdn52bnPED+BF8EMuJ8onbcvAeTngONgAxQkF9/8eVMUsnwg4ePkICLG/zt/Hx7e/+/vck8dKFvHjnwnhXmHs8hLSzZZhHtL7+86UXCxZYlx9UYKelT9GwrhlVoZxQ6OEW2lrFi/ZXc+w/vzi8VeMoWxIzz4+788h4yMDwEHs2Bzpj/0xe31NkNd+8/nFsYPOg2hwi0tRtfeoc6+NE+sD87+5Sx49pQxzt37+Do72Ts90ndd4Cacw0kj2EW2BlwOrc1mwtOXMr2FADdBsrpy9e0WSW5BeXZeWWAnLxtCOW5BRWvvxOE57XcuIv2wMLh9wAG8dhHN7fAi2/T+q4eBvtgjMAGnyA78/MHDu3JydcUlWYVlGSiFCJl8dbs7DzN2/6vpe8JlL97iJr1AasAFShABQpPLhUAQwWUen0nBvP8934ciHmRCPkREfMcCeXlN9iUxCPXb8POXVVgmcwKAarxg4NLBWDFUD71pTf/4R3iy+twP3oL9/w63Atr/J934898M/cwWbb7PWq2WwDtAVPRiUs5ngJor338yafvHe/tM33SO/JfPYALn/Sch/Rd/FiYPx6qm7h+68F3EDi0VAC2GCeKiMvD7TgnrelLqO1PrDEKt3bwSxcoNPCq+jmS5v20nKusAhuGWejqgd3jea1XM2a+xTnwRFE2zlACga/n/6oCMQnchOKAdyeSdw6Lmy5IQLm9P6Z6gSpjXM2pUNHOg+SsXzLypjAheXZwbQstsFWdqtk7uu2rr+/BiB4C6CwEJm83s0gJvqJzJYS46ltfwXlmDumvx/C3YEp9DGdM95+gPjI20A2dhaDAekrf1fdgE8sFwuIK/ZpM0rrTCQ1nEuuHEqqNMW6q+uJqT0Uo9jRyS0ciy4cw7Ew7gJNlZ+Y4w4vtN+7A8eA5J4DE4DT2eHFGQzfwVUhUYGIJ8cEsnOOXzoRP2xam0YHn1gbpLU3zKwSiE8IEW7xqzwkrewVVfdFVxpjGs0k7zok8qR0UVvbFgOcTD4HcqfDiqZsrBJ6ycQQUUemCAJLEYzh/ztts09eu/QrsPPoz7Jyz50cvXf0Q1nFdrVGBwee/USBUkIsKRAOB6hUCYFDVnoqv6hVU90V7CkyGl0x6CqDt3bj5hcU2ZndabQ6L3Y3T4pyyB3H8pGVBM4/gxRtNYvYRnJHSs7Y274bz48OHf56Hs4p0W90umDoyraElFFgTZFhNIDaRG5PnUzecWNUHxkzsdmPcjnPincMSFJfAYDyit7wHlgiAyyEoO3v0//gc5pW1P/jxG99/+Y3vv/jaPwFeeh3wvVff/pGimnH/4Q0PAZhcSVlDYeXeT/9wa2zC/tEvP2ZEiHe9Z3Cl/mSJwKo9wOXTuLq1laejSjvDtnaFlXVza05H1w7F1J6OrhmKaTyXtGNYBHoAHWBPE5hDbj/MYxfi5Jzawxk1R3Q1h3WVBzQV+1QV+9WV+9WK2pCi4xF3Z75AfrIoUFW940Vv+UZu1Q+eX/vcTzZhfrjmsH5gNYFg/UTzSoGCkuzClqQT1tIjpoIj5oKj5kK9tUxvKzPYth23ljYOi8GTyfZTseV94MGLj2Fn2NkZNuDA3DIVUfLtzoGj47lV/bzb9xcEFoZHZdXOn/jKmFlnccK9RPGh9VEtHec+AJ/PzT72FHhhbWCbvQHsP3o063aArL7BLx4+ur/PrGkYTqg5HQeyrzQKMKxcJ0pwuh08TNy4vYrAE3g3CG8IkRLdgdOiNC9c3Ii/+9BTAI662vqalzYmheR+wMi6FJJzGauc7DZ9Ng+vAEgvIQIdncZ/eY1Vox8G+3DKdWeP9ANk2Ya0eO+ru7E6Su7R0G2D/C3d7PxeDoaZPwUecFj5UyQ1eFCy3EAuq8jPYU5oseoGqlHCSKH5G+7OwnMA3oHPP0EFGpoqXtwgZGimyanjNKXFJ2G08/wfED14x4ee6B2dvf/68wh5xbXx6d/1DZ6/fQu5C0buS10CS0EP2e3bt70D1yt30koGw9O7aLoeOiKQDwUC1TZmunXVHvimjckLDi/adHcOOYk9eqChqfLFDQmMtEvgmQM8OvomTXRd/OM82gMLV+L2jt4X1obTtFOb+F2Y57wmp6/Crzyud8s2ND64G8XSN2p2MUoHIzK66Fk9jGUCluu37s/DQO4DveJQLHwIqjGjoMCdR9fnF3rg0Syc+KHAxkQ6KqC2AoHO878Hn88+gj999GcoCQXWRTK0TqrC4is48eHHiCHsotXbdT3Q3LmFY0AB0ANQoHtBgJk3FaSxUlSOuEJHUFiadwDDB8cNIFHwNG8c1dcTAt2XSPcjMiA/e/vHkaXezSOqBHVoINWfxsaRmQF0DmGD789+jk2hqp1AgKK0BqdaovIuc1P2ehOYfiSOL5GNDeKu2UD46eYY8C1VMUVVXYopmqYJijf5swhkKjUES2EBAhCwICaZ6Q8AfwbS/NZ4/XtaC6t4IFzXycjqYoK70WlWwTQzfxoIBCnteNnE66SyVzbJXtmYviaY6hP9Q6+oF714L7jxj3nVP+bnKMSEtaJmEoglzMfThW+zRRtDkjaykzawEjf7hDeQlfCxlawADpag1KnNgqM/IyheJ6hfJ6hew6vfICi9IraTFVZKqpOcaiOmONZSa19+O9WXzWJL3mIkbWAmveOGKlxHAcRDOPLNBUfDSwY52b1BuX1kDAM8euc4QEnV2qlqO01jp2snmWlOmvrDyLID8oNR0n1C6f44F+/FZXSxs3qYWd0sQGYXI72dVtQXXn82of5sYt1ZYd3ZhIbh+KpBWUjmCEUJgthoICZAZWdmTHLyrrDzLrPzLnHyLrHzpkG7FDXSqNpO19iZaQ6y8kPN3uYmE7/2XEL9sBCl7lx8xQAP0h9V3h+1rS+yrC88Qx+XekisOCLCoG/gcFITO90RlukMzQBMcXRTYZlTdKUDnzhKEKGY8UnjRNmw+L2Q5OMB8vcJ8vfxKIoTJI2erNYHI5A1hkDlcU5YzghHdyk0w4EEdIZlTVJUVn+RGSuBbyyxyHtLnNQckuFAr6TIxdTCSrsiaa7KMgamd4XoupiQbhYYKurF+MGqk5S0Tiy3tMFXOIWVjGLQ/7bCWOmuxkIzJoGDSyBp1P0yD580QZIPiw8yk4/7Q4FjeIDsGA4IaA0gbxSKttUlEKq7jIZCo1FUFpwIfU0PwYnHCNJRjs7dqDM00x6iuSZprsjuJ+m62Iv/TO9iLgSHqPXU9E5s+NYGv8RJkPaiAOwBVyy0yWm6aokAARGQoALH3AKwB7QGiodAEBDg5pg4ywWsngLw/SQUcFVA6jhDNB8hAsS/osDi61S0B1YIoD3wjALulR5/A4EVQ2iaAQREZqJ4lIC8TiWIxgPl56DAiUUBORQI1Bioaj1Fo6eA6BpDsOJYWJinAHJGIUPoaQKhTxHQu+LDJk7S0jvw4aXLBCRmts7JzZwMg8DsuVnTdOUUVmjDJSwgdBDEZvFBVvJxP5n7HHgfr9TjQcS0dhRCegdWradzc0dCMy6DUKEIYVnT1GVDSAQF0COFws2aZGt/IWmuzB4gZXQvCPSEZHYz0zoIaaAJWBK07aTMXu+Islq/BCBgwhDlNmKyBRCUMhGcMu4idSxQ5uTkHYxrEsU0pMQ2JiOkxO5MTjlBVRoCFfpgpT5YcZKsasMJm9WcLSfDCg+FFh4JLTgSVniYk3+MojAFg4Cp7oDjgckWQrKVkGyDLULsJLkN1nGTOkaSTMfWbk3vwalb6Zo2qqaNpu0gK05wQ/PfZece4GzZjxKat5eiGSDI7CBtDHw1jax0gDODyISCF1/0j3eElZYrO9amnPRO0W9GSTX4oItx0JfMKj0trTOAt73KL/ETnOwyVnoVBSe9goPHGJw2Y3AaACUYPHILIdVJgG2hwPfhcDkQui4IVJaY/OOd/OoSbZe/Ug+bUILZs5UkPxJFEE1g4ydxCQ4wChAmiVJHYCp8YY4IIBAkrnVIcHRKTAEJk2El21P0XrKjRBky38PyGEFpCHZlDxugajsDBLXbcNKrRIWFCC6oKCk2HIzmsaQCnEhAIMVTAD1q44unhMQcIJwUbC/VdgWAyEgTFHVbYPLRqED5BXQxBTwVxWaSeIwkt6Jv/BcF8JJlsabCSqpTDZuQvLEL4FYVwEqugbxBci7AoX1WAY9zGhWAPRCA9oBbgCQbAZfRxWUhogmSzLVkYZmAuwdgLCigXyGgByOHokQ5SdN04Pg15VjJB39JABlCf1nAKagu1XZiQWRkSRNV3RqUfIRHkl1EBFwXJaJonCRb0QMEyQTSoRCCZBSbMB1WWqMwbAbDJvk4bgG8pp2g7SCiaNoDdb3e0fVbl/YAzBIvHSeAOHBFFGCCAPIDZ/ASAXgOLGlUOhognI6uLdL1bda0g+AEUIJzLPV4aKBshJBkIS4EJIktYAJwCRCT7RC5DSe6iBOdR8GLz/nHW9iF22THN4oPYyVH/BH8JIcDkt6NTmpJSNotBCS2JIr38riljXjpJSJczgFXkaFryXCiC/ik8x4Mg/wIKUDSCutA4OwHqnk0OhwgHOMW14vfi0vYI0ncKwYk7UuMb1YTJWfxiRcJMA4Ie4GQdIEoHYOr18AshFwHRsH9nE905+ICQoF+c0QfWZcevedVXtN6XvNbAP6utVE7NvvHHvQK7/SObENo94poDYgdIEqRlZ5SFLho0iu6bbPAsFkAylZY8g0BwgGiBFwTTWCGQAAXtYtLVi0K2rz4ev/4UzjxBFY8ugCYG81e0e2uOCh8g1/CaZx0DKTtupBhxSZvgVugzQe0HWGk6HRAgN+8Hllbup7fspa30ysg/oB3VLsPX+/DM/jwDT5RJwPijEg2rqWZcHWm+IJXTCtoZjO/DWUTDwgMEiXwZFhgFL986TFcNxqQACb4xXWjBOkITnJxs6DDHQqN5icEAuOLd6NPEeCtKsBDBHgGbyjQT5SY3dmvLgB7YJmAeYUAXBMKOoogNSPZu1buAgGvlQKwBybAzfn/SiDy7yCwuPT4G3rgFNID/y/wfy7wP8LzBi5r4zYhAAAAAElFTkSuQmCC\"}").unwrap();
@@ -120,4 +995,1858 @@ mod tests {
         assert_eq!(server_response.version.protocol, 758);
         assert_eq!(server_response.version.name, "Velocity 1.7.2-1.18.2");
     }
+
+    #[test]
+    fn release_name() {
+        assert_eq!(earthmc_status().version.release_name(), "1.18.2");
+    }
+
+    #[test]
+    fn motd_spans() {
+        let description = earthmc_status().description;
+        let spans = description.spans();
+        assert_eq!(spans[0].text, "E");
+        assert_eq!(spans[0].color, Some(McColor::Aqua));
+        assert!(spans[0].bold);
+        assert_eq!(spans[5].color.unwrap().to_rgb(), (0x55, 0xFF, 0x55));
+        assert_eq!(description.plain_text(), "EarthMC\nSlava Ukraini!");
+        assert_eq!(
+            McColor::from_name("#1A2B3C"),
+            Some(McColor::Custom(0x1A, 0x2B, 0x3C))
+        );
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let status = earthmc_status();
+        let round_trip = ServerStatus::from_json(&serde_json::to_string(&status).unwrap()).unwrap();
+        assert_eq!(
+            round_trip.description.plain_text(),
+            "EarthMC\nSlava Ukraini!"
+        );
+        assert_eq!(round_trip, status);
+    }
+
+    #[test]
+    fn detect_velocity() {
+        let software = earthmc_status().detect_software();
+        assert_eq!(software, ServerSoftware::Velocity);
+        assert!(software.is_proxy());
+    }
+
+    #[test]
+    fn first_line() {
+        assert_eq!(earthmc_status().description.first_line(), "EarthMC");
+        assert_eq!(
+            parse_json(&status_json("Top\\r\\nBottom"))
+                .unwrap()
+                .description
+                .first_line(),
+            "Top"
+        );
+        assert_eq!(
+            parse_json(&status_json(""))
+                .unwrap()
+                .description
+                .first_line(),
+            ""
+        );
+    }
+
+    #[test]
+    fn fill_ratio() {
+        // Reserved slots let online go past max, the ratio isn't clamped
+        let players = earthmc_status().players;
+        let fill_ratio = players.fill_ratio().unwrap();
+        assert!((fill_ratio - 196.0 / 150.0).abs() < 1e-9);
+        assert!(players.is_full());
+
+        let players = |online: i64, max: i64| Players {
+            online,
+            max,
+            sample: vec![],
+        };
+        assert_eq!(players(5, 20).fill_ratio(), Some(0.25));
+        assert!(!players(5, 20).is_full());
+        assert!(players(20, 20).is_full());
+        // No usable max, or a negative count, gives no ratio and never counts as full
+        assert_eq!(players(0, 0).fill_ratio(), None);
+        assert!(!players(3, 0).is_full());
+        assert_eq!(players(3, -1).fill_ratio(), None);
+        assert_eq!(players(-1, 20).fill_ratio(), None);
+        assert!(!players(-1, 20).is_full());
+    }
+
+    #[test]
+    fn address_validation() {
+        assert_eq!(
+            validate_address(" Mc.Example.com ", 25565).unwrap(),
+            "mc.example.com"
+        );
+        assert_eq!(validate_address("[::1]", 25565).unwrap(), "::1");
+        assert!(validate_address("", 25565).is_err());
+        assert!(validate_address("mc example.com", 25565).is_err());
+        assert!(validate_address("mc.example.com", 0).is_err());
+        assert!(validate_address("::1::2", 25565).is_err());
+
+        assert_eq!(
+            resolve("[::1]", DEFAULT_PORT).unwrap(),
+            [SocketAddr::from((Ipv6Addr::LOCALHOST, DEFAULT_PORT))]
+        );
+        assert!(matches!(
+            resolve("", DEFAULT_PORT),
+            Err(QueryError::InvalidAddress { .. })
+        ));
+    }
+
+    #[test]
+    fn split_response() {
+        // Every VarInt and the payload arrive split across many segments
+        let json = &status_json("Split");
+        let port = mock_server(status_response(json), Duration::from_millis(2));
+        let status = server_status("127.0.0.1", port).unwrap();
+        assert_eq!(status.players.online, 3);
+        assert_eq!(status.description.text, "Split");
+
+        let port = mock_server(status_response(json), Duration::ZERO);
+        let status = server_status_streaming("127.0.0.1", port).unwrap();
+        assert_eq!(status.players.online, 3);
+        // A server stalling mid-payload is a socket error, not bad JSON
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let stalled = status_response(json);
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]);
+            stream.write_all(&stalled[..stalled.len() - 4]).unwrap();
+            thread::sleep(Duration::from_millis(500));
+        });
+        let error = StatusQuery::new("127.0.0.1", port)
+            .timeout(Duration::from_millis(100))
+            .server_status_streaming()
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<io::Error>().unwrap().kind(),
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+        ));
+        let port = mock_server(status_response(&json.replace(':', ";")), Duration::ZERO);
+        let error = server_status_streaming("127.0.0.1", port).unwrap_err();
+        assert!(error
+            .downcast_ref::<serde_json::Error>()
+            .unwrap()
+            .is_syntax());
+
+        let port = mock_server(status_response(json), Duration::ZERO);
+        let timings = full_status("127.0.0.1", port).unwrap().timings;
+        assert_eq!(
+            timings.bytes_sent,
+            status_packet_builder(DEFAULT_PROTOCOL, "127.0.0.1", port).len()
+        );
+        assert_eq!(timings.bytes_received, status_response(json).len());
+        assert_eq!(timings.frame_length, status_response(json).len() - 1);
+    }
+
+    #[test]
+    fn packet_limit() {
+        let port = default_status_server();
+        let query = StatusQuery::new("127.0.0.1", port).max_packet_size(Some(16));
+        assert!(query.server_status().is_err());
+
+        let port = default_status_server();
+        let query = StatusQuery::new("127.0.0.1", port)
+            .max_packet_size(Some(16))
+            .no_packet_limit();
+        assert_eq!(query.server_status().unwrap().players.online, 3);
+        // String length VarInt of -1, behind a plausible frame length and packet id
+        let port = mock_server(
+            vec![0x06, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F],
+            Duration::ZERO,
+        );
+        let error = StatusQuery::new("127.0.0.1", port)
+            .no_packet_limit()
+            .server_status()
+            .unwrap_err();
+        assert!(matches!(
+            QueryError::from_boxed(error),
+            QueryError::InvalidLength { length: -1 }
+        ));
+    }
+
+    #[test]
+    fn status_v1() {
+        let status = parse_json("{\"description\":{\"text\":\"Hi \",\"extra\":[\"there\"]},\"players\":{\"online\":1,\"max\":20,\"sample\":[{\"id\":\"069a79f4-44e9-4726-a5be-fca90e38aaf5\",\"name\":\"Notch\"}]},\"version\":{\"protocol\":758,\"name\":\"Paper 1.18.2\"},\"modinfo\":{\"type\":\"FML\",\"modList\":[{\"modid\":\"forge\",\"version\":\"14.23.5\"}]}}").unwrap();
+        let v1 = StatusV1::from(status);
+        assert_eq!(v1.motd, "Hi there");
+        assert_eq!(v1.favicon, None);
+        assert_eq!(v1.player_sample[0].name, "Notch");
+        assert_eq!(v1.release_name.as_deref(), Some("1.18.2"));
+        assert_eq!(v1.mods[0].version.as_deref(), Some("14.23.5"));
+
+        let json = serde_json::to_string(&v1).unwrap();
+        assert!(json.contains("\"players_online\":1"));
+        assert_eq!(serde_json::from_str::<StatusV1>(&json).unwrap(), v1);
+    }
+
+    #[test]
+    fn not_minecraft() {
+        let port = mock_server(b"HTTP/1.1 400 Bad Request\r\n\r\n".to_vec(), Duration::ZERO);
+        let error = server_status("127.0.0.1", port).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<QueryError>(),
+            Some(QueryError::NotAMinecraftServer { protocol: "HTTP" })
+        ));
+
+        let port = mock_server(vec![0x16, 0x03, 0x01, 0x00, 0x05], Duration::ZERO);
+        assert!(matches!(
+            probe("127.0.0.1", port, TIMEOUT),
+            Err(QueryError::NotAMinecraftServer { protocol: "TLS" })
+        ));
+    }
+
+    #[test]
+    fn server_list_entry() {
+        let status = parse_json("{\"description\":{\"text\":\"A Server\\nsecond line\"},\"favicon\":\"data:image/png;base64,iVBO\\nRw==\",\"players\":{\"online\":1,\"max\":2},\"version\":{\"protocol\":758,\"name\":\"1.18.2\"}}").unwrap();
+        let entry = status.server_list_entry("mc.example.com:25566");
+        assert_eq!(entry.name, "A Server");
+        assert_eq!(entry.ip, "mc.example.com:25566");
+        assert_eq!(entry.icon.as_deref(), Some("iVBORw=="));
+
+        #[cfg(feature = "nbt")]
+        {
+            let nbt = servers_dat(&[entry]);
+            assert_eq!(&nbt[..3], [0x0A, 0x00, 0x00]); // Unnamed root compound
+            assert_eq!(&nbt[3..13], b"\x09\x00\x07servers");
+            assert_eq!(&nbt[13..18], [0x0A, 0x00, 0x00, 0x00, 0x01]); // One compound
+            assert_eq!(&nbt[18..27], b"\x08\x00\x04name\x00\x08");
+            assert_eq!(nbt[nbt.len() - 2..], [0x00, 0x00]);
+        }
+    }
+
+    #[test]
+    fn network_filter() {
+        let cidr = Cidr::parse("10.0.0.0/8").unwrap();
+        assert!(cidr.contains("10.1.2.3".parse().unwrap()));
+        assert!(cidr.contains("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(!cidr.contains("11.0.0.1".parse().unwrap()));
+        assert!(Cidr::parse("2001:db8::/32")
+            .unwrap()
+            .contains("2001:db8::1".parse().unwrap()));
+        assert!(Cidr::parse("0.0.0.0/0")
+            .unwrap()
+            .contains("8.8.8.8".parse().unwrap()));
+        assert!(Cidr::parse("10.0.0.0/33").is_err());
+        assert!(Cidr::parse("example.com/8").is_err());
+
+        let filter = NetworkFilter::default()
+            .allow("192.168.0.0/16")
+            .unwrap()
+            .deny("192.168.1.0/24")
+            .unwrap();
+        assert!(filter.permits("192.168.2.1".parse().unwrap()));
+        assert!(!filter.permits("192.168.1.1".parse().unwrap()));
+        assert!(!filter.permits("8.8.8.8".parse().unwrap()));
+        assert!(!NetworkFilter::default()
+            .deny_reserved()
+            .permits("::1".parse().unwrap()));
+
+        let port = default_status_server();
+        let client = ScanClient::with_filter(
+            1,
+            TIMEOUT,
+            Pacing::default(),
+            NetworkFilter::default().deny_reserved(),
+        );
+        let results: Vec<TypedScanResult> = client
+            .scan_channel([Target::new("127.0.0.1", port)])
+            .iter()
+            .collect();
+        assert!(matches!(
+            results[0].1,
+            Err(QueryError::TargetDenied { ip }) if ip.is_loopback()
+        ));
+    }
+
+    #[test]
+    fn port_types() {
+        assert_eq!(StatusPort::default(), StatusPort(DEFAULT_PORT));
+        assert_eq!(QueryPort::default(), QueryPort(DEFAULT_QUERY_PORT));
+        assert_eq!(BedrockPort::default().0, 19132);
+        assert_eq!(StatusQuery::new("localhost", 25566).port, 25566);
+        assert_eq!(
+            StatusQuery::new("localhost", StatusPort::default()).port,
+            25565
+        );
+
+        // The free functions take either form too
+        assert_eq!(
+            server_status_streaming("127.0.0.1", StatusPort(default_status_server()))
+                .unwrap()
+                .players
+                .online,
+            3
+        );
+        assert!(probe("127.0.0.1", StatusPort(default_status_server()), TIMEOUT).is_ok());
+    }
+
+    #[test]
+    fn early_status() {
+        let favicon = format!("data:image/png;base64,{}", "A".repeat(100_000));
+        let json = format!("{{\"version\":{{\"protocol\":758,\"name\":\"1.18.2\"}},\"players\":{{\"online\":1,\"max\":2}},\"description\":{{\"text\":\"Late\"}},\"favicon\":\"{}\"}}", favicon);
+        let port = mock_server(status_response(&json), Duration::ZERO);
+        let early = super::early_status("127.0.0.1", port).unwrap();
+        assert_eq!(early.version.protocol, 758);
+        assert_eq!(early.players.max, 2);
+        assert!(early.description.is_none());
+        assert!(!early.complete);
+
+        // Favicon first, everything has to be read
+        let json = format!("{{\"favicon\":\"{}\",\"description\":{{\"text\":\"Early\"}},\"players\":{{\"online\":1,\"max\":2}},\"version\":{{\"protocol\":758,\"name\":\"1.18.2\"}}}}", favicon);
+        let port = mock_server(status_response(&json), Duration::ZERO);
+        let early = super::early_status("127.0.0.1", port).unwrap();
+        assert_eq!(early.description.unwrap().text, "Early");
+        assert!(early.complete);
+
+        let port = mock_server(
+            status_response("{\"players\":{\"online\":1,\"max\":2}}"),
+            Duration::ZERO,
+        );
+        assert!(super::early_status("127.0.0.1", port).is_err());
+    }
+
+    #[test]
+    fn sample_cap() {
+        let sample = (0..MAX_SAMPLE_PLAYERS + 5)
+            .map(|i| {
+                format!(
+                    "{{\"name\":\"p{}\",\"id\":\"00000000-0000-0000-0000-000000000000\"}}",
+                    i
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let json = format!("{{\"description\":{{\"text\":\"\"}},\"players\":{{\"online\":1,\"max\":2,\"sample\":[{}]}},\"version\":{{\"protocol\":758,\"name\":\"1.18.2\"}}}}", sample);
+        let status = parse_json(&json).unwrap();
+        assert_eq!(status.players.sample.len(), MAX_SAMPLE_PLAYERS);
+        assert_eq!(status.players.sample.last().unwrap().name, "p999");
+
+        let (status, warnings) = parse_json_lenient(&json).unwrap();
+        assert_eq!(status.players.sample.len(), MAX_SAMPLE_PLAYERS);
+        assert!(warnings
+            .iter()
+            .any(|warning| warning.field == "players.sample"));
+
+        let port = mock_server(status_response(&json), Duration::ZERO);
+        let status = StatusQuery::new("127.0.0.1", port)
+            .max_sample_players(3)
+            .server_status()
+            .unwrap();
+        assert_eq!(status.players.sample.len(), 3);
+        assert_eq!(
+            parse_json(&json).unwrap().players.sample.len(),
+            MAX_SAMPLE_PLAYERS
+        ); // Restored afterwards
+
+        let panicked = std::panic::catch_unwind(|| with_sample_cap(3, || panic!("mid parse")));
+        assert!(panicked.is_err());
+        assert_eq!(server_object::sample_cap(), MAX_SAMPLE_PLAYERS); // Restored by the guard too
+    }
+
+    #[test]
+    fn capture() {
+        let response = status_response(&status_json(""));
+        let port = mock_server(response.clone(), Duration::ZERO);
+        let full = StatusQuery::new("127.0.0.1", port)
+            .capture()
+            .full_status()
+            .unwrap();
+        let transcript = full.transcript.unwrap();
+        assert_eq!(
+            transcript.sent,
+            status_packet_builder(DEFAULT_PROTOCOL, "127.0.0.1", port)
+        );
+        assert_eq!(transcript.received, response);
+
+        let port = default_status_server();
+        let full = StatusQuery::new("127.0.0.1", port).full_status().unwrap();
+        assert!(full.transcript.is_none());
+    }
+
+    #[test]
+    fn split_writes() {
+        let port = default_status_server();
+        let full = StatusQuery::new("127.0.0.1", port)
+            .split_writes()
+            .full_status()
+            .unwrap();
+        assert_eq!(
+            full.timings.bytes_sent,
+            status_packet_builder(DEFAULT_PROTOCOL, "127.0.0.1", port).len()
+        );
+
+        let query = StatusQuery::new("a", 25565).split_writes();
+        let packets = query.status_packets("a");
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[1], status_request_builder());
+        assert_eq!(packets.concat(), query.status_packet("a"));
+    }
+
+    #[test]
+    fn socket_ttl() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let options = SocketOptions {
+            ttl: Some(7),
+            ..SocketOptions::default()
+        };
+        let stream = options
+            .connect(&listener.local_addr().unwrap(), TIMEOUT)
+            .unwrap();
+        assert_eq!(stream.ttl().unwrap(), 7);
+
+        let port = default_status_server();
+        assert!(StatusQuery::new("127.0.0.1", port)
+            .ttl(7)
+            .server_status()
+            .is_ok());
+    }
+
+    #[test]
+    fn source_port() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let source_port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port(); // Free again once the listener is dropped
+        let options = SocketOptions {
+            source_port: Some(source_port),
+            ..SocketOptions::default()
+        };
+        let stream = options
+            .connect(&listener.local_addr().unwrap(), TIMEOUT)
+            .unwrap();
+        assert_eq!(stream.local_addr().unwrap().port(), source_port);
+        assert_eq!(listener.accept().unwrap().1.port(), source_port);
+
+        let taken = listener.local_addr().unwrap().port(); // Still listening
+        let port = mock_server(status_response("{}"), Duration::ZERO);
+        let error = StatusQuery::new("127.0.0.1", port)
+            .source_port(taken)
+            .server_status()
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<QueryError>(),
+            Some(QueryError::SourcePortInUse { port }) if *port == taken
+        ));
+    }
+
+    #[test]
+    fn custom_connector() {
+        // Port 1 is never dialed, the connector sends every connection to the mock server
+        let port = mock_server(status_response(&status_json("Injected")), Duration::ZERO);
+        let status = StatusQuery::new("127.0.0.1", 1)
+            .connector(move |_, timeout| {
+                TcpStream::connect_timeout(&SocketAddr::from(([127, 0, 0, 1], port)), timeout)
+            })
+            .server_status()
+            .unwrap();
+        assert_eq!(status.description.text, "Injected");
+    }
+
+    #[test]
+    fn address_fallback() {
+        let closed = || {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap() // Refuses connections once the listener is dropped
+        };
+        let port = mock_server(status_response(&status_json("Fallback")), Duration::ZERO);
+        let status = StatusQuery::new("127.0.0.1", port)
+            .socket_addrs(vec![closed(), SocketAddr::from(([127, 0, 0, 1], port))])
+            .server_status()
+            .unwrap();
+        assert_eq!(status.description.text, "Fallback");
+
+        let error = StatusQuery::new("127.0.0.1", port)
+            .socket_addrs(vec![closed()])
+            .server_status()
+            .unwrap_err();
+        assert!(matches!(
+            QueryError::from_boxed(error),
+            QueryError::ConnectionRefused
+        ));
+    }
+
+    #[test]
+    fn connect_failure_kinds() {
+        // Every address refused, like a dual stack host with the port closed on both
+        let closed = || {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap()
+        };
+        let error = StatusQuery::new("127.0.0.1", 1)
+            .socket_addrs(vec![closed(), closed()])
+            .server_status()
+            .unwrap_err();
+        assert!(matches!(
+            QueryError::from_boxed(error),
+            QueryError::ConnectionRefused
+        ));
+
+        let failed = |kinds: &[io::ErrorKind]| {
+            QueryError::connect_failed(kinds.iter().map(|kind| io::Error::from(*kind)).collect())
+        };
+        use io::ErrorKind::{ConnectionRefused, Other, TimedOut};
+        assert!(matches!(
+            failed(&[TimedOut, TimedOut, TimedOut]),
+            QueryError::Timeout
+        ));
+        match failed(&[ConnectionRefused, TimedOut]) {
+            QueryError::AllAddressesFailed { errors } => assert_eq!(errors.len(), 2),
+            error => panic!("unexpected error: {}", error),
+        }
+        assert!(matches!(
+            failed(&[Other, Other]),
+            QueryError::AllAddressesFailed { .. }
+        ));
+        assert!(matches!(failed(&[Other]), QueryError::Io(_)));
+    }
+
+    #[test]
+    fn json_end() {
+        let padded = format!("{}{}", status_json("Padded"), "x".repeat(100_000));
+        let query = |port| StatusQuery::new("127.0.0.1", port);
+
+        let port = mock_server(status_response(&padded), Duration::ZERO);
+        assert!(query(port).server_status().is_err());
+
+        let port = mock_server(status_response(&padded), Duration::ZERO);
+        let full = query(port)
+            .stop_at_json_end(TrailingData::Ignore)
+            .full_status()
+            .unwrap();
+        assert_eq!(full.status.description.text, "Padded");
+        assert!(full.timings.bytes_received < 100_000);
+
+        let port = mock_server(status_response(&padded), Duration::ZERO);
+        assert!(query(port)
+            .stop_at_json_end(TrailingData::Reject)
+            .server_status()
+            .is_err());
+
+        // The streaming and buffer reusing paths honour it as well
+        let port = mock_server(status_response(&padded), Duration::ZERO);
+        let status = query(port)
+            .stop_at_json_end(TrailingData::Ignore)
+            .server_status_streaming()
+            .unwrap();
+        assert_eq!(status.description.text, "Padded");
+        let port = mock_server(status_response(&padded), Duration::ZERO);
+        let mut buffer = vec![];
+        let status = query(port)
+            .stop_at_json_end(TrailingData::Ignore)
+            .server_status_into(&mut buffer)
+            .unwrap();
+        assert_eq!(status.description.text, "Padded");
+        assert!(buffer.is_empty());
+        let port = mock_server(status_response(&padded), Duration::ZERO);
+        assert!(query(port)
+            .stop_at_json_end(TrailingData::Reject)
+            .server_status_streaming()
+            .is_err());
+
+        let port = mock_server(status_response(&padded), Duration::ZERO);
+        assert!(query(port)
+            .stop_at_json_end(TrailingData::Ignore)
+            .raw_status_frame()
+            .is_err());
+    }
+
+    #[test]
+    fn read_call_limit() {
+        let trickled = || mock_server(status_response(&status_json("")), Duration::from_millis(1));
+        let query = |port| StatusQuery::new("127.0.0.1", port).max_read_calls(10);
+        let too_many = |error: Box<dyn error::Error + Send + Sync>| {
+            matches!(
+                QueryError::from_boxed(error),
+                QueryError::TooManyReads { max_reads: 10 }
+            )
+        };
+        assert!(too_many(query(trickled()).server_status().unwrap_err()));
+        assert!(too_many(
+            query(trickled()).server_status_streaming().unwrap_err()
+        ));
+        assert!(too_many(
+            query(trickled())
+                .server_status_into(&mut vec![])
+                .unwrap_err()
+        ));
+        assert!(too_many(query(trickled()).raw_status_frame().unwrap_err()));
+        assert!(too_many(query(trickled()).early_status().unwrap_err()));
+        #[cfg(feature = "tokio")]
+        {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            let error = runtime
+                .block_on(query(trickled()).server_status_async())
+                .unwrap_err();
+            assert!(too_many(error));
+        }
+
+        // Only payload reads count, the header's VarInts are read a byte at a time
+        let status = StatusQuery::new("127.0.0.1", default_status_server())
+            .max_read_calls(1)
+            .server_status()
+            .unwrap();
+        assert_eq!(status.players.online, 3);
+    }
+
+    #[test]
+    fn frame_length_mismatch() {
+        let port = mock_server(status_response("{}"), Duration::ZERO);
+        assert_eq!(
+            raw_status_frame("127.0.0.1", port).unwrap(),
+            RawStatusFrame {
+                frame_length: 4,
+                packet_id: 0,
+                json: "{}".into()
+            }
+        );
+
+        let mut response = status_response("{}");
+        response[0] += 1; // Claims one more byte than the packet holds
+        let port = mock_server(response, Duration::ZERO);
+        let error = server_status("127.0.0.1", port).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<QueryError>(),
+            Some(QueryError::FrameLengthMismatch {
+                frame_length: 5,
+                expected: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn vhosts() {
+        // Answers each connection with the handshake hostname as the MOTD, like a proxy routing on it
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for stream in listener.incoming().take(2) {
+                let mut stream = stream.unwrap();
+                let mut reader = FrameReader::new(&mut stream, None);
+                reader.read_varint().unwrap(); // Handshake length
+                reader.read_varint().unwrap(); // Packet id
+                reader.read_varint().unwrap(); // Protocol
+                let vhost = String::from_utf8(reader.read_frame().unwrap()).unwrap();
+                stream
+                    .write_all(&status_response(&status_json(&vhost)))
+                    .unwrap();
+            }
+        });
+
+        let results = query_vhosts(
+            "127.0.0.1",
+            &["lobby.example.com", "survival.example.com"],
+            port,
+        )
+        .unwrap();
+        assert_eq!(results.len(), 2);
+        for vhost in ["lobby.example.com", "survival.example.com"] {
+            assert_eq!(results[vhost].as_ref().unwrap().description.text, vhost);
+        }
+        assert!(matches!(
+            query_vhosts("", &["a"], port),
+            Err(QueryError::InvalidAddress { .. })
+        ));
+
+        let packet = StatusQuery::new("127.0.0.1", 25565)
+            .handshake_hostname("lobby")
+            .status_packet("127.0.0.1");
+        assert_eq!(
+            packet,
+            status_packet_builder(DEFAULT_PROTOCOL, "lobby", 25565)
+        );
+    }
+
+    #[test]
+    fn closed_early() {
+        for response in [vec![], vec![0x05], vec![0x05, 0x80]] {
+            let port = mock_server(response, Duration::ZERO);
+            let error = server_status("127.0.0.1", port).unwrap_err();
+            assert!(matches!(
+                error.downcast_ref::<QueryError>(),
+                Some(QueryError::ConnectionClosedEarly)
+            ));
+        }
+        let port = mock_server(vec![], Duration::ZERO);
+        assert!(matches!(
+            probe("127.0.0.1", port, TIMEOUT),
+            Err(QueryError::ConnectionClosedEarly)
+        ));
+
+        // Once the header is in, a short payload is a plain EOF
+        let mut response = status_response("{}");
+        response.pop();
+        let port = mock_server(response, Duration::ZERO);
+        let error = server_status("127.0.0.1", port).unwrap_err();
+        assert!(error.downcast_ref::<io::Error>().is_some());
+    }
+
+    #[test]
+    fn over_long_var_ints() {
+        // Accepted, vanilla's reader only caps the byte count
+        assert_eq!(var_int_read_sized(&mut &[0x80, 0x00][..]).unwrap(), (0, 2));
+        assert_eq!(
+            var_int_read_sized(&mut &[0x81, 0x80, 0x80, 0x80, 0x00][..]).unwrap(),
+            (1, 5)
+        );
+        assert!(var_int_read(&mut &[0x80, 0x80, 0x80, 0x80, 0x80, 0x00][..]).is_err());
+
+        let json = status_json("Long");
+        let mut packet = vec![0x80, 0x00]; // Packet id 0 in two bytes
+        packet.extend(var_int_pack(json.as_bytes().to_vec()));
+        let port = mock_server(var_int_pack(packet), Duration::ZERO);
+        let frame = raw_status_frame("127.0.0.1", port).unwrap();
+        assert_eq!(frame.packet_id, 0);
+        assert_eq!(parse_json(&frame.json).unwrap().description.text, "Long");
+    }
+
+    #[test]
+    fn reused_buffer() {
+        let mut buffer = Vec::with_capacity(1024);
+        for text in ["First", "Second"] {
+            let json = status_json(text);
+            let port = mock_server(status_response(&json), Duration::ZERO);
+            let status = server_status_into("127.0.0.1", port, &mut buffer).unwrap();
+            assert_eq!(status.description.text, text);
+            assert_eq!(buffer.len(), json.len());
+            assert_eq!(buffer.capacity(), 1024);
+        }
+    }
+
+    #[test]
+    fn replayed_frame() {
+        let frame = status_response(&status_json("Captured"));
+        let status = parse_status_frame(&frame).unwrap();
+        assert_eq!(status.description.text, "Captured");
+        assert!(parse_status_frame(&frame[..frame.len() - 1]).is_err());
+        assert!(parse_status_frame(b"HTTP/1.1 200 OK").is_err());
+    }
+
+    #[test]
+    fn address_splitting() {
+        assert_eq!(
+            split_address("mc.example.com").unwrap(),
+            ("mc.example.com", 25565)
+        );
+        assert_eq!(
+            split_address("mc.example.com:25566").unwrap(),
+            ("mc.example.com", 25566)
+        );
+        assert_eq!(split_address("[::1]:25566").unwrap(), ("[::1]", 25566));
+        assert_eq!(split_address("[::1]").unwrap(), ("[::1]", 25565));
+        assert_eq!(split_address("::1").unwrap(), ("::1", 25565));
+        assert!(split_address("mc.example.com:port").is_err());
+    }
+
+    #[test]
+    fn handshake() {
+        assert_eq!(
+            build_handshake(758, "a", 25565, 0x02),
+            vec![0x08, 0x00, 0xF6, 0x05, 0x01, b'a', 0x63, 0xDD, 0x02]
+        );
+        assert_eq!(var_int_encode(-1), vec![0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+    }
+
+    #[test]
+    fn malformed_version() {
+        let players = "\"players\":{\"online\":1,\"max\":2},\"description\":{\"text\":\"\"}";
+        let status = parse_json(&format!("{{\"version\":\"Paper 1.20\",{}}}", players)).unwrap();
+        assert_eq!(status.version.name, "Paper 1.20");
+        assert_eq!(status.version.protocol, 0);
+
+        let status =
+            parse_json(&format!("{{\"version\":{{\"protocol\":763}},{}}}", players)).unwrap();
+        assert_eq!(status.version.release_name(), "1.20.1");
+    }
+
+    #[test]
+    fn scan() {
+        let targets: Vec<Target> = (0..3)
+            .map(|_| Target::new("127.0.0.1", default_status_server()))
+            .chain([Target::new("", 25565)])
+            .collect();
+
+        let client = ScanClient::new(2);
+        let results: Vec<ScanResult> = client.scan(targets).collect();
+        assert_eq!(results.len(), 4);
+        assert_eq!(
+            results.iter().filter(|(_, status)| status.is_ok()).count(),
+            3
+        );
+
+        let port = default_status_server();
+        let receiver =
+            client.scan_channel([Target::new("127.0.0.1", port), Target::new("", 25565)]);
+        let mut results: Vec<TypedScanResult> = receiver.iter().collect();
+        results.sort_by_key(|(target, _)| target.hostname.clone());
+        assert!(matches!(
+            results[0].1,
+            Err(QueryError::InvalidAddress { .. })
+        ));
+        assert!(results[1].1.is_ok());
+    }
+
+    #[test]
+    fn rate_limited_scan() {
+        let targets: Vec<Target> = (0..3)
+            .map(|_| Target::new("127.0.0.1", default_status_server()))
+            .collect();
+
+        // All targets share 127.0.0.1, so the per-host bucket spaces them 100ms apart
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let limiter = RateLimiter::new(100.0)
+            .per_host(10.0)
+            .clock(Arc::clone(&clock));
+        let client = ScanClient::with_rate_limit(3, TIMEOUT, limiter);
+        let start = clock.now();
+        let results: Vec<ScanResult> = client.scan(targets).collect();
+        assert!(results.iter().all(|(_, status)| status.is_ok()));
+        assert!(clock.now() - start >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn rate_limiter_pruning() {
+        // Buckets refill within nanoseconds at this rate, so every sweep empties the map
+        let limiter = RateLimiter::new(1e9).per_host(1e9);
+        for host in 0..5000u32 {
+            limiter.reserve(std::net::IpAddr::from(host.to_be_bytes()));
+        }
+        assert!(limiter.tracked_hosts() < 2048);
+    }
+
+    #[test]
+    fn styled_root() {
+        let status = parse_json("{\"description\":{\"text\":\"Welcome \",\"color\":\"gold\",\"bold\":true,\"extra\":[\"home\",{\"text\":\"!\",\"bold\":false}]},\"players\":{\"online\":1,\"max\":2},\"version\":{\"protocol\":758,\"name\":\"1.18.2\"}}").unwrap();
+        let spans = status.description.spans();
+        assert_eq!(spans.len(), 3);
+        assert!(spans.iter().all(|span| span.color == Some(McColor::Gold)));
+        assert!(spans[0].bold && spans[1].bold && !spans[2].bold);
+        assert_eq!(status.description.plain_text(), "Welcome home!");
+    }
+
+    #[test]
+    fn jittered_scan() {
+        let targets: Vec<Target> = (0..2)
+            .map(|_| Target::new("127.0.0.1", default_status_server()))
+            .collect();
+
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let client = ScanClient::with_pacing(
+            2,
+            TIMEOUT,
+            Pacing {
+                jitter: Some(Duration::from_millis(50)..Duration::from_millis(100)),
+                clock: clock.clone(),
+                ..Pacing::default()
+            },
+        );
+        let start = clock.now();
+        let results: Vec<ScanResult> = client.scan(targets).collect();
+        assert!(results.iter().all(|(_, status)| status.is_ok()));
+        // Both connects waited at least the minimum jitter
+        let waited = clock.now() - start;
+        assert!(waited >= Duration::from_millis(100) && waited < Duration::from_millis(200));
+    }
+
+    #[test]
+    fn target_parsing() {
+        assert_eq!(
+            Target::parse("mc.example.com").unwrap(),
+            Target::new("mc.example.com", 25565)
+        );
+        assert_eq!(
+            Target::parse("[::1]:25566").unwrap(),
+            Target::new("[::1]", 25566)
+        );
+        assert!(Target::parse("host:port").is_err());
+    }
+
+    #[test]
+    fn display_width() {
+        let status = parse_json("{\"description\":{\"text\":\"§a你好 server\\n§be\\u0301\"},\"players\":{\"online\":1,\"max\":2},\"version\":{\"protocol\":758,\"name\":\"1.18.2\"}}").unwrap();
+        assert_eq!(status.description.display_width(), 11);
+    }
+
+    #[test]
+    fn legacy_codes() {
+        let players =
+            "\"players\":{\"online\":1,\"max\":2},\"version\":{\"protocol\":758,\"name\":\"\"}";
+        let status = parse_json(&format!(
+            "{{\"description\":{{\"text\":\"§aHello §l§cWorld\",\"extra\":[{{\"text\":\"!\",\"underlined\":true,\"extra\":[\"§m?\"]}}]}},{}}}",
+            players
+        ))
+        .unwrap();
+        let spans = status.description.spans();
+        assert_eq!(spans.len(), 4);
+        assert_eq!(spans[0].text, "Hello ");
+        assert_eq!(spans[0].color, Some(McColor::Green));
+        assert_eq!(spans[1].text, "World");
+        assert_eq!(spans[1].color, Some(McColor::Red));
+        assert!(!spans[1].bold);
+        assert!(spans[2].underlined && !spans[2].strikethrough);
+        assert!(spans[3].underlined && spans[3].strikethrough);
+        assert_eq!(status.description.plain_text(), "Hello World!?");
+    }
+
+    #[test]
+    fn renderers() {
+        let status = parse_json("{\"description\":{\"text\":\"\",\"extra\":[{\"text\":\" Big <Sale> \",\"color\":\"red\",\"bold\":true},\"now\"]},\"players\":{\"online\":1,\"max\":2},\"version\":{\"protocol\":758,\"name\":\"1.18.2\"}}").unwrap();
+        let description = &status.description;
+        assert_eq!(description.render_with(&PlainText), " Big <Sale> now");
+        assert_eq!(
+            description.render_with(&Ansi),
+            "\x1b[38;2;255;85;85;1m Big <Sale> \x1b[0mnow"
+        );
+        assert_eq!(
+            description.render_with(&Html),
+            "<span style=\"color:#ff5555;font-weight:bold\"> Big &lt;Sale&gt; </span>now"
+        );
+        assert_eq!(
+            description.render_with(&Markdown),
+            " **Big \\<Sale\\>** now"
+        );
+
+        struct Shouting;
+        impl MotdRenderer for Shouting {
+            fn render(&self, spans: &[Span]) -> String {
+                spans.iter().map(|span| span.text.to_uppercase()).collect()
+            }
+        }
+        assert_eq!(description.render_with(&Shouting), " BIG <SALE> NOW");
+    }
+
+    #[test]
+    fn redirects() {
+        let target = "{\"version\":{\"protocol\":758,\"name\":\"\"},\"players\":{\"online\":7,\"max\":20},\"description\":{\"text\":\"Real\"}}";
+        let query = || {
+            let target_port = mock_server(status_response(target), Duration::ZERO);
+            let lobby = format!("{{\"version\":{{\"protocol\":758,\"name\":\"\"}},\"players\":{{\"online\":0,\"max\":0}},\"description\":{{\"text\":\"\"}},\"redirect\":{}}}", target_port);
+            let lobby_port = mock_server(status_response(&lobby), Duration::ZERO);
+            StatusQuery::new("127.0.0.1", lobby_port).follow_redirects(1, |json| {
+                let port = json.get("redirect")?.as_u64()?;
+                Some(("127.0.0.1".to_string(), port as u16))
+            })
+        };
+
+        let status = query().server_status().unwrap();
+        assert_eq!(status.players.online, 7);
+        #[cfg(feature = "tokio")]
+        {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            let status = runtime.block_on(query().server_status_async()).unwrap();
+            assert_eq!(status.players.online, 7);
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn blocking_async_parity() {
+        // Every scenario runs against both paths, which have to agree on the status or the error
+        let json = &status_json("Parity");
+        let scenarios = [
+            ("valid", Some(status_response(json)), Duration::ZERO),
+            (
+                "split",
+                Some(status_response(json)),
+                Duration::from_millis(1),
+            ),
+            (
+                "oversized",
+                Some(vec![0x10, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x07]),
+                Duration::ZERO,
+            ),
+            ("bad varint", Some(vec![0x80; 6]), Duration::ZERO),
+            ("empty", Some(status_response("")), Duration::ZERO),
+            ("hung up", Some(vec![0x05]), Duration::ZERO),
+            (
+                "frame mismatch",
+                Some(vec![0x05, 0x00, 0x02, b'{', b'}']),
+                Duration::ZERO,
+            ),
+            (
+                "negative length",
+                Some(vec![0x06, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F]),
+                Duration::ZERO,
+            ),
+            ("rejected", None, Duration::ZERO),
+        ];
+
+        type Outcome = Result<String, (std::mem::Discriminant<QueryError>, String)>;
+        fn outcome(result: Result<ServerStatus, Box<dyn error::Error + Send + Sync>>) -> Outcome {
+            // Errors compare by QueryError variant as well as by message
+            result
+                .map(|status| serde_json::to_string(&status).unwrap())
+                .map_err(|error| {
+                    let error = QueryError::from_boxed(error);
+                    (std::mem::discriminant(&error), error.to_string())
+                })
+        }
+
+        for (name, response, delay) in scenarios {
+            let port = || match &response {
+                Some(response) => mock_server(response.clone(), delay),
+                None => {
+                    // Bind then drop, so nothing is listening on the port
+                    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+                    listener.local_addr().unwrap().port()
+                }
+            };
+            let blocking = outcome(server_status("127.0.0.1", port()));
+            let non_blocking = outcome(server_status_async("127.0.0.1", port()).await);
+            assert_eq!(blocking, non_blocking, "{}", name);
+            assert_eq!(
+                blocking.is_ok(),
+                name == "valid" || name == "split",
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn batch_async() {
+        let targets: Vec<Target> = (0..4)
+            .map(|_| Target::new("127.0.0.1", default_status_server()))
+            .chain([Target::new("", 25565)])
+            .collect();
+
+        let results = server_status_batch_async(targets, 2).await;
+        assert_eq!(results.len(), 5);
+        assert_eq!(
+            results.iter().filter(|(_, status)| status.is_ok()).count(),
+            4
+        );
+
+        let targets: Vec<Target> = (0..3)
+            .map(|_| Target::new("127.0.0.1", default_status_server()))
+            .collect();
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let limiter = RateLimiter::new(100.0)
+            .per_host(10.0)
+            .clock(Arc::clone(&clock));
+        let start = clock.now();
+        let results = server_status_batch_async_limited(targets, 3, limiter).await;
+        assert!(results.iter().all(|(_, status)| status.is_ok()));
+        assert!(clock.now() - start >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn handshake_fields() {
+        let packet = StatusQuery::new("a", 25565)
+            .extra_handshake_fields(vec!["ip".into(), "uuid".into()])
+            .handshake_port(1)
+            .status_packet("a");
+        assert_eq!(
+            packet,
+            status_packet_builder(DEFAULT_PROTOCOL, "a\0ip\0uuid", 1)
+        );
+    }
+
+    #[test]
+    fn handshake_styles() {
+        let bungee = HandshakeStyle::BungeeForwarding {
+            client_ip: "203.0.113.7".into(),
+            uuid: "069a79f444e94726a5befca90e38aaf5".into(),
+            properties: None,
+        };
+        assert_eq!(
+            bungee.hostname("mc.example.com"),
+            "mc.example.com\x00203.0.113.7\x00069a79f444e94726a5befca90e38aaf5"
+        );
+        let packet = StatusQuery::new("a", 25565)
+            .handshake_style(bungee.clone())
+            .status_packet("a");
+        assert_eq!(
+            packet,
+            status_packet_builder(DEFAULT_PROTOCOL, &bungee.hostname("a"), 25565)
+        );
+
+        for style in [HandshakeStyle::Vanilla, HandshakeStyle::VelocityModern] {
+            assert_eq!(style.hostname("mc.example.com"), "mc.example.com");
+            let packet = StatusQuery::new("a", 25565)
+                .extra_handshake_fields(vec!["ip".into()])
+                .handshake_style(style)
+                .status_packet("a");
+            assert_eq!(packet, status_packet_builder(DEFAULT_PROTOCOL, "a", 25565));
+        }
+    }
+
+    #[test]
+    fn proxy_protocol_prefix() {
+        let header = ProxyProtocol::V1 {
+            source: "192.0.2.1:50000".parse().unwrap(),
+            destination: "198.51.100.2:25565".parse().unwrap(),
+        };
+        let packet = StatusQuery::new("a", 25565)
+            .proxy_protocol(header)
+            .status_packet("a");
+        assert_eq!(
+            packet,
+            [
+                header.header(),
+                status_packet_builder(DEFAULT_PROTOCOL, "a", 25565)
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn lenient_parse() {
+        let (status, warnings) = parse_json_lenient(
+            "{\"description\":42,\"players\":{\"max\":\"20\"},\"version\":{\"name\":\"1.20\"}}",
+        )
+        .unwrap();
+        assert_eq!(status.description.text, "42");
+        assert_eq!(status.players.max, 20);
+        assert_eq!(status.players.online, 0);
+        let fields: Vec<&str> = warnings
+            .iter()
+            .map(|warning| warning.field.as_str())
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                "description",
+                "players.max",
+                "players.online",
+                "version.protocol"
+            ]
+        );
+
+        let (_, warnings) = parse_json_lenient("{\"version\":{\"protocol\":758,\"name\":\"1.18.2\"},\"players\":{\"online\":3,\"max\":20},\"description\":\"Fine\"}").unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn version_list() {
+        // Multi-version proxies send every version they accept, the first one is used
+        let json = "{\"version\":[{\"protocol\":763,\"name\":\"1.20.1\"},{\"protocol\":762,\"name\":\"1.19.4\"},\"1.18.2\"],\"players\":{\"online\":3,\"max\":20},\"description\":{\"text\":\"Multi\"}}";
+        assert_eq!(parse_json(json).unwrap().version.protocol, 763);
+        let (status, warnings) = parse_json_lenient(json).unwrap();
+        assert_eq!(status.version.name, "1.20.1");
+        assert_eq!(
+            warnings[0].message,
+            "was a list, using the first entry (alternatives: 1.19.4, 1.18.2)"
+        );
+    }
+
+    #[test]
+    fn parse_report() {
+        let report = try_parse("{\"description\":\"Bare\",\"players\":{\"online\":\"3\",\"max\":20},\"version\":{\"protocol\":758,\"name\":\"1.18.2\"}}").unwrap();
+        assert!(!report.is_conformant());
+        assert!(report.strict_error.is_some());
+        assert_eq!(report.status.players.online, 3);
+        assert_eq!(report.warnings[0].field, "players.online");
+
+        let report = try_parse("{\"description\":{\"text\":\"\"},\"players\":{\"online\":3,\"max\":20},\"version\":{\"protocol\":758,\"name\":\"1.18.2\"}}").unwrap();
+        assert!(report.is_conformant());
+        assert!(try_parse("[]").is_err());
+    }
+
+    #[test]
+    fn chat_security() {
+        let players =
+            "\"players\":{\"online\":1,\"max\":2},\"version\":{\"protocol\":760,\"name\":\"1.19.2\"}";
+        let status = parse_json(&format!(
+            "{{\"description\":{{\"text\":\"\"}},\"enforcesSecureChat\":true,\"previewsChat\":false,{}}}",
+            players
+        ))
+        .unwrap();
+        assert_eq!(
+            status.chat_security(),
+            ChatSecurity {
+                enforces_secure_chat: Some(true),
+                previews_chat: Some(false)
+            }
+        );
+
+        let status = parse_json(&format!(
+            "{{\"description\":{{\"text\":\"\"}},\"enforcesSecureChat\":\"TRUE\",\"previewsChat\":[],{}}}",
+            players
+        ))
+        .unwrap();
+        assert_eq!(
+            status.chat_security(),
+            ChatSecurity {
+                enforces_secure_chat: Some(true),
+                previews_chat: None
+            }
+        );
+
+        let (status, warnings) = parse_json_lenient(&format!(
+            "{{\"description\":\"\",\"enforcesSecureChat\":\"yes\",{}}}",
+            players
+        ))
+        .unwrap();
+        assert_eq!(status.chat_security(), ChatSecurity::default());
+        assert_eq!(warnings[0].field, "enforcesSecureChat");
+    }
+
+    #[test]
+    fn identity_hash() {
+        let status = |online: i64, motd: &str| {
+            parse_json(&format!("{{\"description\":{{\"text\":\"{}\"}},\"players\":{{\"online\":{},\"max\":20}},\"version\":{{\"protocol\":758,\"name\":\"1.18.2\"}}}}", motd, online)).unwrap()
+        };
+        assert_eq!(
+            status(1, "Hello").identity_hash(),
+            status(5, "Hello").identity_hash()
+        );
+        assert_ne!(
+            status(1, "Hello").identity_hash(),
+            status(1, "Goodbye").identity_hash()
+        );
+        assert_eq!(status(1, "Hello").identity_hash(), 0x8632bac2161744f1); // Must never change
+    }
+
+    #[test]
+    fn identity_hash_normalized() {
+        let status = |motd: &str| {
+            parse_json(&format!("{{\"description\":{{\"text\":\"{}\"}},\"players\":{{\"online\":1,\"max\":20}},\"version\":{{\"protocol\":758,\"name\":\"1.18.2\"}}}}", motd)).unwrap()
+        };
+        assert_eq!(
+            status("Welcome %player%! 12:05, {online} online")
+                .description
+                .normalize_placeholders(),
+            "Welcome {}! #:#, {} online"
+        );
+        assert_eq!(
+            status("100% {not a placeholder}")
+                .description
+                .normalize_placeholders(),
+            "#% {not a placeholder}"
+        );
+        assert_eq!(
+            status("Up 12 days").identity_hash_normalized(),
+            status("Up 13 days").identity_hash_normalized()
+        );
+        assert_ne!(
+            status("Up 12 days").identity_hash(),
+            status("Up 13 days").identity_hash()
+        );
+    }
+
+    #[test]
+    fn motd_links() {
+        let status = parse_json("{\"description\":{\"text\":\"\",\"clickEvent\":{\"action\":\"open_url\",\"value\":\"https://example.com\"},\"extra\":[\"Join \",{\"text\":\"Discord\",\"clickEvent\":{\"action\":\"open_url\",\"value\":\"https://discord.gg/x\"}}]},\"players\":{\"online\":1,\"max\":2},\"version\":{\"protocol\":758,\"name\":\"1.18.2\"}}").unwrap();
+        assert_eq!(
+            status.description.links(),
+            [
+                ("open_url".to_string(), "https://example.com".to_string()),
+                ("open_url".to_string(), "https://discord.gg/x".to_string())
+            ]
+        );
+        assert_eq!(status.description.plain_text(), "Join Discord");
+    }
+
+    #[test]
+    fn geyser() {
+        // Unknown top level keys from the bridge don't stop parsing
+        let json = "{\"description\":{\"text\":\"Bridged\"},\"players\":{\"online\":1,\"max\":2},\"version\":{\"protocol\":763,\"name\":\"Geyser 2.2.0 (1.20.40 - 1.20.50)\"},\"bedrockLevel\":\"world\"}";
+        let status = parse_json(json).unwrap();
+        assert_eq!(status.detect_software(), ServerSoftware::Geyser);
+        assert_eq!(
+            status.geyser_info(),
+            Some(GeyserInfo {
+                geyser_version: Some("2.2.0".into()),
+                bedrock_versions: Some("1.20.40 - 1.20.50".into()),
+            })
+        );
+
+        let mut status = status;
+        status.version.name = "1.20.1".into();
+        assert_eq!(status.geyser_info(), None);
+    }
+
+    #[test]
+    fn status_history() {
+        let mut status = parse_json("{\"description\":{\"text\":\"\"},\"players\":{\"online\":0,\"max\":20},\"version\":{\"protocol\":758,\"name\":\"1.18.2\"}}").unwrap();
+        let mut history = StatusHistory::new(Duration::from_secs(60));
+        assert_eq!(history.average(), None);
+
+        let clock = ManualClock::new(Duration::from_secs(30)); // One poll every 30 seconds
+        for online in [4, 10, 7, 1] {
+            status.players.online = online;
+            history.push(&status, clock.now());
+        }
+        // The first poll is 90 seconds behind the newest, outside the window
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.online_series()[0].1, 10);
+        assert_eq!(history.min(), Some(1));
+        assert_eq!(history.max(), Some(10));
+        assert_eq!(history.average(), Some(6.0));
+    }
+
+    #[test]
+    fn plausibility() {
+        let json = "{\"description\":{\"text\":\"\"},\"players\":{\"online\":3,\"max\":20},\"version\":{\"protocol\":758,\"name\":\"Paper 1.18.2\"}}";
+        let status = parse_json(json).unwrap();
+        assert_eq!(status.plausibility().score, 100);
+
+        let notch = "{\"id\":\"069a79f4-44e9-4726-a5be-fca90e38aaf5\",\"name\":\"Notch\"}";
+        let fake = format!("{{\"description\":{{\"text\":\"\"}},\"players\":{{\"online\":2,\"max\":2147483647,\"sample\":[{},{}]}},\"version\":{{\"protocol\":0,\"name\":\"xkcd\"}}}}", notch, notch);
+        let plausibility = parse_json(&fake).unwrap().plausibility();
+        assert_eq!(plausibility.score, 10);
+        assert_eq!(
+            plausibility.reasons,
+            [
+                "max players is absurdly large",
+                "version has neither a version number nor a protocol",
+                "every player in the sample has the same UUID"
+            ]
+        );
+    }
+
+    #[test]
+    fn favicon() {
+        // 2x1 PNG, red then blue
+        let json = "{\"description\":{\"text\":\"\"},\"favicon\":\"data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAIAAAABCAIAAAB7QOjdAAAADUlEQVR4nGP4zwAE/wEHAAH/4iOeWQAAAABJRU5ErkJggg==\",\"players\":{\"online\":1,\"max\":2},\"version\":{\"protocol\":758,\"name\":\"1.18.2\"}}";
+        let status = parse_json(json).unwrap();
+        let png = status.favicon_bytes().unwrap().unwrap();
+        assert_eq!(favicon::png_dimensions(&png), Some((2, 1)));
+
+        let (_, warnings) = parse_json_lenient(json).unwrap();
+        assert_eq!(warnings[0].message, "is 2x1, expected 64x64");
+
+        #[cfg(feature = "image")]
+        {
+            let image = status.favicon_image().unwrap().unwrap().to_rgb8();
+            assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0]);
+            assert_eq!(image.get_pixel(1, 0).0, [0, 0, 255]);
+        }
+
+        let mut status = status;
+        status.favicon = String::new();
+        assert_eq!(status.favicon_bytes().unwrap(), None);
+        status.favicon = "data:image/png;base64,!!".into();
+        assert!(status.favicon_bytes().is_err());
+    }
+
+    #[test]
+    fn favicon_fallbacks() {
+        // Nested objects and the "icon" key seen from some forks and proxies
+        let players = "\"players\":{\"online\":1,\"max\":2},\"version\":{\"protocol\":758,\"name\":\"1.18.2\"},\"description\":{\"text\":\"\"}";
+        let nested = format!(
+            "{{\"favicon\":{{\"data\":\"data:image/png;base64,AA==\"}},{}}}",
+            players
+        );
+        assert_eq!(
+            parse_json(&nested).unwrap().favicon,
+            "data:image/png;base64,AA=="
+        );
+        let unknown = format!("{{\"favicon\":{{\"size\":64}},{}}}", players);
+        assert_eq!(parse_json(&unknown).unwrap().favicon, "");
+        let (_, warnings) = parse_json_lenient(&unknown).unwrap();
+        assert_eq!(warnings[0].message, "was not a string, ignored");
+
+        let icon = format!("{{\"icon\":\"data:image/png;base64,AA==\",{}}}", players);
+        let (status, warnings) = parse_json_lenient(&icon).unwrap();
+        assert_eq!(status.favicon, "data:image/png;base64,AA==");
+        assert_eq!(warnings[0].message, "was sent as \"icon\", used that");
+        assert_eq!(
+            parse_json(&icon).unwrap().favicon,
+            "data:image/png;base64,AA=="
+        );
+
+        let both = format!(
+            "{{\"favicon\":\"data:image/png;base64,AA==\",\"icon\":\"data:image/png;base64,AQ==\",{}}}",
+            players
+        );
+        assert_eq!(
+            parse_json(&both).unwrap().favicon,
+            "data:image/png;base64,AA=="
+        );
+        let (status, warnings) = parse_json_lenient(&both).unwrap();
+        assert_eq!(status.favicon, "data:image/png;base64,AA==");
+        assert_eq!(
+            warnings[0].message,
+            "was sent along with \"icon\", ignored the icon"
+        );
+    }
+
+    #[test]
+    fn favicon_spec_compliance() {
+        // Only the IHDR is looked at, so a PNG cut off right after it is enough
+        let mut status = earthmc_status();
+        assert_eq!(status.favicon_is_spec_compliant(), None);
+        status.favicon = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAEAAAABA".into(); // 64x64
+        assert_eq!(status.favicon_is_spec_compliant(), Some(true));
+        status.favicon = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAIAAAAB".into(); // 2x1
+        assert_eq!(status.favicon_is_spec_compliant(), Some(false));
+        status.favicon = "data:image/png;base64,!!".into();
+        assert_eq!(status.favicon_is_spec_compliant(), Some(false));
+    }
+
+    #[test]
+    fn decorated_sample() {
+        let status = parse_json("{\"description\":{\"text\":\"\"},\"players\":{\"online\":2,\"max\":20,\"sample\":[{\"id\":\"069a79f4-44e9-4726-a5be-fca90e38aaf5\",\"name\":{\"text\":\"[Admin] \",\"extra\":[\"Notch\"]},\"prefix\":\"Admin\"},{\"id\":\"853c80ef-3c37-49fd-aa49-938b674adae6\",\"name\":\"jeb_\"}]},\"version\":{\"protocol\":758,\"name\":\"1.18.2\"}}").unwrap();
+        let sample = &status.players.sample;
+        assert_eq!(sample[0].name, "[Admin] Notch");
+        assert_eq!(sample[0].extra["prefix"], "Admin");
+        assert_eq!(sample[1].name, "jeb_");
+        assert!(sample[1].extra.is_empty());
+
+        let json = serde_json::to_string(&status).unwrap();
+        assert!(json.contains("\"prefix\":\"Admin\""));
+        #[cfg(feature = "bincode")]
+        assert_eq!(
+            ServerStatus::from_bincode(&status.to_bincode().unwrap()).unwrap(),
+            status
+        );
+    }
+
+    #[test]
+    fn real_players() {
+        let status = parse_json("{\"description\":{\"text\":\"\"},\"players\":{\"online\":5,\"max\":20,\"sample\":[{\"id\":\"069a79f4-44e9-4726-a5be-fca90e38aaf5\",\"name\":\"Notch\"},{\"id\":\"00000000-0000-0000-0000-000000000000\",\"name\":\"§6Join our Discord!\"},{\"id\":\"not-a-uuid\",\"name\":\"www.example.com\"},{\"id\":\"853c80ef-3c37-49fd-aa49-938b674adae6\",\"name\":\"§cAd line\"}]},\"version\":{\"protocol\":758,\"name\":\"1.18.2\"}}").unwrap();
+        let real: Vec<&str> = status
+            .players
+            .real_players()
+            .iter()
+            .map(|sample| sample.name.as_str())
+            .collect();
+        assert_eq!(real, ["Notch"]);
+    }
+
+    #[test]
+    fn online_mode() {
+        let players = |ids: &[&str]| Players {
+            max: 20,
+            online: ids.len() as i64,
+            sample: ids
+                .iter()
+                .map(|id| Sample {
+                    id: id.to_string(),
+                    name: String::new(),
+                    extra: HashMap::new(),
+                })
+                .collect(),
+        };
+        let online = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+        let offline = "d1a0c4b6-1ac1-3d6e-9d1f-1a4e4b9e2a1c";
+        let nil = "00000000-0000-0000-0000-000000000000";
+        assert_eq!(players(&[online, nil]).likely_online_mode(), Some(true));
+        assert_eq!(players(&[offline]).likely_online_mode(), Some(false));
+        assert_eq!(players(&[online, offline]).likely_online_mode(), None);
+        assert_eq!(players(&[nil]).likely_online_mode(), None);
+
+        let version = |id: &str| players(&[id]).sample[0].uuid_version();
+        assert_eq!(version(online), Some(4));
+        assert_eq!(version("017f22e2-79b0-7cc3-98c4-dc0c0c07398f"), Some(7));
+        for undefined in ["0", "9", "f"] {
+            let id = format!("069a79f4-44e9-{}726-a5be-fca90e38aaf5", undefined);
+            assert_eq!(version(&id), None);
+        }
+    }
+
+    #[test]
+    fn named_sample() {
+        let sample = |id: &str, name: &str| Sample {
+            id: id.to_string(),
+            name: name.to_string(),
+            extra: HashMap::new(),
+        };
+        let players = Players {
+            max: 20,
+            online: 10,
+            sample: vec![
+                sample("069a79f4-44e9-4726-a5be-fca90e38aaf5", "Notch"),
+                sample("00000000-0000-0000-0000-000000000000", "Anonymous Player"),
+                sample("853c80ef-3c37-49fd-aa49-938b674adae6", "Anonymous Player"),
+            ],
+        };
+        let (named, hidden_count) = players.named_sample();
+        assert_eq!(named.len(), 1);
+        assert_eq!(named[0].name, "Notch");
+        assert_eq!(hidden_count, 9);
+    }
+
+    #[test]
+    fn login_modes() {
+        let check = |response: Vec<u8>| {
+            let port = mock_server(response, Duration::ZERO);
+            login_check("127.0.0.1", port, 763, "Notch").unwrap()
+        };
+        // Encryption Request, only the packet id is looked at
+        assert_eq!(check(vec![0x02, 0x01, 0x00]), LoginMode::OnlineMode);
+        assert_eq!(check(vec![0x02, 0x03, 0x00]), LoginMode::OfflineMode);
+        // A Disconnect is framed like a status response
+        assert_eq!(
+            check(status_response("{\"text\":\"Whitelisted\"}")),
+            LoginMode::Rejected {
+                reason: "{\"text\":\"Whitelisted\"}".into()
+            }
+        );
+        assert_eq!(
+            check(vec![0x02, 0x04, 0x00]),
+            LoginMode::Other { packet_id: 0x04 }
+        );
+    }
+
+    #[test]
+    fn scan_outcomes() {
+        let outcome = scan_outcome("127.0.0.1", pong_server(true), TIMEOUT);
+        assert_eq!(outcome.bits(), 0x0F);
+        assert_eq!(
+            outcome.to_string(),
+            "reachable|minecraft|valid-json|latency"
+        );
+
+        let port = default_status_server();
+        let outcome = scan_outcome("127.0.0.1", port, TIMEOUT);
+        assert!(outcome.contains(ScanOutcome::VALID_JSON));
+        assert!(!outcome.contains(ScanOutcome::LATENCY));
+
+        let port = mock_server(b"HTTP/1.1 400 Bad Request\r\n\r\n".to_vec(), Duration::ZERO);
+        assert_eq!(
+            scan_outcome("127.0.0.1", port, TIMEOUT),
+            ScanOutcome::REACHABLE
+        );
+        assert_eq!(scan_outcome("", port, TIMEOUT).bits(), 0);
+    }
+
+    #[test]
+    fn probing() {
+        let port = mock_server(status_response("{}"), Duration::ZERO);
+        assert!(probe("127.0.0.1", port, TIMEOUT).is_ok());
+
+        let port = mock_server(vec![0x01, 0x02], Duration::ZERO);
+        assert!(matches!(
+            probe("127.0.0.1", port, TIMEOUT),
+            Err(QueryError::InvalidResponse { .. })
+        ));
+    }
+
+    #[test]
+    fn empty_response() {
+        let port = mock_server(status_response(""), Duration::ZERO);
+        let error = server_status("127.0.0.1", port).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<QueryError>(),
+            Some(QueryError::EmptyResponse)
+        ));
+    }
+
+    #[test]
+    fn connect_time() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(tcp_connect_time("127.0.0.1", port, TIMEOUT).unwrap() < TIMEOUT);
+
+        // Goes through the query's connector like every other query
+        let connects = Arc::new(AtomicUsize::new(0));
+        let counted = connects.clone();
+        let query = StatusQuery::new("127.0.0.1", 1).connector(move |_, timeout| {
+            counted.fetch_add(1, Ordering::SeqCst);
+            TcpStream::connect_timeout(&SocketAddr::from(([127, 0, 0, 1], port)), timeout)
+        });
+        assert_eq!(
+            query
+                .clock(ManualClock::new(Duration::from_millis(7)))
+                .tcp_connect_time()
+                .unwrap(),
+            Duration::from_millis(7)
+        );
+        assert_eq!(connects.load(Ordering::SeqCst), 1);
+
+        drop(listener);
+        assert!(matches!(
+            tcp_connect_time("127.0.0.1", port, TIMEOUT),
+            Err(QueryError::ConnectionRefused)
+        ));
+    }
+
+    #[test]
+    fn query_probe() {
+        // Same connector and handshake as a full query, the server sees the overridden hostname
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let read = stream.read(&mut request).unwrap();
+            stream.write_all(&status_response("{}")).unwrap();
+            request[..read].to_vec()
+        });
+        let query = StatusQuery::new("127.0.0.1", 1)
+            .handshake_hostname("lobby.example.com")
+            .handshake_port(25565)
+            .connector(move |_, timeout| {
+                TcpStream::connect_timeout(&SocketAddr::from(([127, 0, 0, 1], port)), timeout)
+            });
+        query.probe().unwrap();
+        assert_eq!(server.join().unwrap(), query.status_packet("127.0.0.1"));
+    }
+
+    #[test]
+    fn translatable() {
+        let status = parse_json("{\"description\":{\"translate\":\"motd\",\"with\":[\"Steve\",{\"text\":\"5\",\"color\":\"red\"}]},\"players\":{\"online\":1,\"max\":2},\"version\":{\"protocol\":758,\"name\":\"\"}}").unwrap();
+        assert_eq!(status.description.plain_text(), "motd");
+
+        let translations =
+            HashMap::from([("motd".to_string(), "Hi %s, %2$s%% online".to_string())]);
+        let spans = status.description.spans_with_translations(&translations);
+        let text: String = spans.iter().map(|span| span.text.as_str()).collect();
+        assert_eq!(text, "Hi Steve, 5% online");
+        assert_eq!(spans[3].color, Some(McColor::Red));
+    }
+
+    proptest::proptest! {
+        // Servers are untrusted, none of the hostile input paths may panic
+        #[test]
+        fn var_int_read_never_panics(bytes in proptest::collection::vec(proptest::num::u8::ANY, 0..16)) {
+            let _ = var_int_read(&mut bytes.as_slice());
+        }
+
+        #[test]
+        fn var_int_round_trips(num in proptest::num::i32::ANY) {
+            proptest::prop_assert_eq!(var_int_read(&mut var_int_encode(num).as_slice()).unwrap(), num);
+        }
+
+        #[test]
+        fn status_response_never_panics(bytes in proptest::collection::vec(proptest::num::u8::ANY, 0..64)) {
+            let _ = read_status_response(&mut bytes.as_slice(), Some(MAX_PACKET_SIZE));
+        }
+
+        #[test]
+        fn parse_json_never_panics(json in ".*") {
+            let _ = parse_json(&json);
+            let _ = parse_json_lenient(&json);
+        }
+
+        #[test]
+        fn parse_json_lenient_never_panics(
+            players in "[0-9a-z\"{}:,\\[\\] ]{0,32}",
+            description in "[0-9a-z\"{}:,\\[\\] ]{0,32}",
+        ) {
+            // Structurally valid JSON with junk in the interesting places
+            let json = format!("{{\"players\":{},\"description\":{}}}", players, description);
+            let _ = parse_json(&json);
+            let _ = parse_json_lenient(&json);
+        }
+    }
+
+    #[test]
+    fn socks5_proxy() {
+        // The mock proxy answers the status itself once the tunnel is "open"
+        let json = "{\"version\":{\"protocol\":758,\"name\":\"\"},\"players\":{\"online\":4,\"max\":20},\"description\":{\"text\":\"\"}}";
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy_port = listener.local_addr().unwrap().port();
+        let response = status_response(json);
+        let requested = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut greeting = [0; 3];
+            stream.read_exact(&mut greeting).unwrap();
+            stream.write_all(&[0x05, 0x00]).unwrap();
+            let mut request = [0; 5];
+            stream.read_exact(&mut request).unwrap();
+            let mut hostname = vec![0; request[4] as usize + 2];
+            stream.read_exact(&mut hostname).unwrap();
+            stream
+                .write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                .unwrap();
+            let _ = stream.read(&mut [0; 1024]);
+            stream.write_all(&response).unwrap();
+            hostname
+        });
+
+        let status = StatusQuery::new("mc.example.com", 25565)
+            .proxy(Proxy::Socks5 {
+                addr: format!("127.0.0.1:{}", proxy_port),
+                auth: None,
+            })
+            .server_status()
+            .unwrap();
+        assert_eq!(status.players.online, 4);
+        assert_eq!(requested.join().unwrap(), b"mc.example.com\x63\xDD");
+    }
+
+    fn http_proxy(reply: &'static [u8], delay: Duration) -> (u16, thread::JoinHandle<String>) {
+        // Mock HTTP CONNECT proxy, returns the request headers it got and then answers the status itself
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy_port = listener.local_addr().unwrap().port();
+        let requested = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut headers = vec![];
+            let mut byte = [0];
+            while !headers.ends_with(b"\r\n\r\n") {
+                stream.read_exact(&mut byte).unwrap();
+                headers.push(byte[0]);
+            }
+            for byte in reply {
+                let _ = stream.write_all(&[*byte]); // Gone once the client gives up
+                thread::sleep(delay);
+            }
+            let _ = stream.read(&mut [0; 1024]);
+            let _ = stream.write_all(&status_response(&status_json("Tunneled")));
+            String::from_utf8(headers).unwrap()
+        });
+        (proxy_port, requested)
+    }
+
+    #[test]
+    fn http_connect_proxy() {
+        let (proxy_port, requested) = http_proxy(b"HTTP/1.1 200 OK\r\n\r\n", Duration::ZERO);
+        let status = StatusQuery::new("mc.example.com", 25565)
+            .proxy(Proxy::HttpConnect {
+                addr: format!("127.0.0.1:{}", proxy_port),
+                auth: Some(("user".into(), "pass".into())),
+            })
+            .server_status()
+            .unwrap();
+        assert_eq!(status.description.text, "Tunneled");
+        assert_eq!(
+            requested.join().unwrap(),
+            "CONNECT mc.example.com:25565 HTTP/1.1\r\nHost: mc.example.com:25565\r\nProxy-Authorization: Basic dXNlcjpwYXNz\r\n\r\n"
+        );
+
+        // Every byte arrives well within the timeout, the negotiation as a whole doesn't
+        let (proxy_port, _) = http_proxy(b"HTTP/1.1 200 OK\r\n\r\n", Duration::from_millis(20));
+        let error = StatusQuery::new("mc.example.com", 25565)
+            .timeout(Duration::from_millis(100))
+            .proxy(Proxy::HttpConnect {
+                addr: format!("127.0.0.1:{}", proxy_port),
+                auth: None,
+            })
+            .server_status()
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<io::Error>().map(io::Error::kind),
+            Some(io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock)
+        ));
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn bincode_round_trip() {
+        let status = parse_json("{\"version\":{\"protocol\":758,\"name\":\"1.18.2\"},\"players\":{\"online\":1,\"max\":20,\"sample\":[{\"id\":\"069a79f4-44e9-4726-a5be-fca90e38aaf5\",\"name\":\"Notch\"}]},\"description\":{\"text\":\"\",\"extra\":[{\"text\":\"Hi\",\"bold\":true}]}}").unwrap();
+        let encoded = status.to_bincode().unwrap();
+        assert_eq!(ServerStatus::from_bincode(&encoded).unwrap(), status);
+    }
+
+    #[test]
+    fn lossy_utf8() {
+        let mut response = b"{\"version\":{\"protocol\":758,\"name\":\"1.18.2\"},\"players\":{\"online\":3,\"max\":20},\"description\":{\"text\":\"Bad \xFF\"}}".to_vec();
+        response = var_int_pack([vec![0x00], var_int_pack(response)].concat());
+
+        let port = mock_server(response.clone(), Duration::ZERO);
+        assert!(server_status("127.0.0.1", port).is_err());
+
+        let port = mock_server(response.clone(), Duration::ZERO);
+        let status = StatusQuery::new("127.0.0.1", port)
+            .lossy_utf8()
+            .server_status()
+            .unwrap();
+        assert_eq!(status.description.text, "Bad \u{FFFD}");
+
+        let port = mock_server(response, Duration::ZERO);
+        let status = StatusQuery::new("127.0.0.1", port)
+            .lossy_utf8()
+            .server_status_streaming()
+            .unwrap();
+        assert_eq!(status.description.text, "Bad \u{FFFD}");
+    }
+
+    fn pong_server(echo: bool) -> u16 {
+        // Answers the status request, then echoes the ping back unless told to go silent
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]); // Handshake and status request
+            stream.write_all(&status_response("{}")).unwrap();
+            let mut ping = [0; 10];
+            let _ = stream.read_exact(&mut ping);
+            if echo {
+                stream.write_all(&ping).unwrap(); // A pong is the ping echoed back
+            } else {
+                thread::sleep(Duration::from_secs(1));
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn ping_pong() {
+        assert!(ping("127.0.0.1", pong_server(true)).is_ok());
+
+        let latency = StatusQuery::new("127.0.0.1", pong_server(true))
+            .clock(ManualClock::new(Duration::from_millis(7)))
+            .ping()
+            .unwrap();
+        assert_eq!(latency, Duration::from_millis(7));
+    }
+
+    #[test]
+    fn single_parse() {
+        let port = default_status_server();
+        let timings = StatusQuery::new("127.0.0.1", port)
+            .clock(ManualClock::new(Duration::from_millis(1)))
+            .full_status()
+            .unwrap()
+            .timings;
+        assert_eq!(timings.connect, Duration::from_millis(1));
+        assert_eq!(timings.parse, Duration::from_millis(1)); // A single parse, straight into ServerStatus
+    }
+
+    #[test]
+    fn pong_checks() {
+        // The whole pong is read, a wrong payload or packet id fails the ping
+        let mut pong = ping_packet_builder(42);
+        assert!(read_pong(&mut pong.as_slice(), 42).is_ok());
+        assert!(read_pong(&mut pong.as_slice(), 43).is_err());
+        pong[1] = 0x00;
+        assert!(read_pong(&mut pong.as_slice(), 42).is_err());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn ping_pong_async() {
+        assert!(server_ping_async("127.0.0.1", pong_server(true))
+            .await
+            .is_ok());
+
+        let silent = StatusQuery::new("127.0.0.1", pong_server(false))
+            .timeout(Duration::from_millis(100))
+            .ping_async()
+            .await;
+        assert!(silent.is_err());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn polling_async() {
+        use futures_core::Stream;
+        use std::pin::Pin;
+
+        let port = default_status_server();
+        let mut statuses = poll_async("127.0.0.1", port, Duration::from_millis(10));
+        async fn next(statuses: &mut StatusStream) -> Result<ServerStatus, QueryError> {
+            std::future::poll_fn(|cx| Pin::new(&mut *statuses).poll_next(cx))
+                .await
+                .unwrap() // The stream never ends
+        }
+
+        assert_eq!(next(&mut statuses).await.unwrap().players.online, 3);
+        // The mock server only answers once, the failure is yielded and polling carries on
+        assert!(matches!(
+            next(&mut statuses).await,
+            Err(QueryError::ConnectionRefused)
+        ));
+        assert!(next(&mut statuses).await.is_err());
+    }
 }
@@ -0,0 +1,85 @@
+use std::error;
+use std::io::prelude::*;
+
+use crate::frame::FrameReader;
+use crate::ports::StatusPort;
+use crate::socket_options::SocketOptions;
+use crate::{build_handshake, connect, resolve, validate_address, var_int_pack, TIMEOUT};
+
+const MAX_DISCONNECT_SIZE: u32 = 1024 * 256; // A kick message, not a status with a favicon
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginMode {
+    OnlineMode,                  // Encryption Request, the server authenticates with Mojang
+    OfflineMode,                 // Set Compression or Login Success straight away
+    Rejected { reason: String }, // Disconnect, the JSON chat component as sent
+    Other { packet_id: i32 },    // e.g. a Login Plugin Request from a Velocity backend
+}
+
+fn login_start_builder(protocol: i32, username: &str) -> Vec<u8> {
+    // The layout changed a few times, https://wiki.vg/Protocol_History. The offline UUID is all zeros,
+    // the server never gets far enough to care.
+    let optional_uuid = match protocol {
+        ..=758 => vec![],
+        759 => vec![0x00],       // No signature data
+        760 => vec![0x00, 0x00], // No signature data, no UUID
+        761..=763 => vec![0x00], // No UUID
+        _ => vec![0; 16],        // UUID is mandatory from 1.20.2
+    };
+    var_int_pack(
+        [
+            vec![0x00], // Packet id
+            var_int_pack(username.as_bytes().to_vec()),
+            optional_uuid,
+        ]
+        .concat(),
+    )
+}
+
+pub fn login_check(
+    hostname: &str,
+    port: impl Into<StatusPort>,
+    protocol: i32,
+    username: &str,
+) -> Result<LoginMode, Box<dyn error::Error + Send + Sync>> {
+    // Starts a login and classifies the first packet back, the connection is dropped before any
+    // authentication happens. protocol has to match the server, -1 only gets an "outdated" kick.
+    let StatusPort(port) = port.into();
+    let hostname = validate_address(hostname, port)?;
+    let socket_addrs = resolve(&hostname, port)?;
+    let mut stream = connect(&socket_addrs, TIMEOUT, &SocketOptions::default())?;
+    stream.write_all(
+        &[
+            build_handshake(protocol, &hostname, port, 0x02),
+            login_start_builder(protocol, username),
+        ]
+        .concat(),
+    )?;
+
+    let mut reader = FrameReader::new(&mut stream, Some(MAX_DISCONNECT_SIZE));
+    let _length = reader.read_varint()?; // Unpack length (unused, only the start of the packet is read)
+    let packet_id = reader.read_varint()?;
+    Ok(match packet_id {
+        0x00 => LoginMode::Rejected {
+            reason: String::from_utf8(reader.read_frame()?)?,
+        },
+        0x01 => LoginMode::OnlineMode,
+        0x02 | 0x03 => LoginMode::OfflineMode,
+        packet_id => LoginMode::Other { packet_id },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn login_start_layouts() {
+        assert_eq!(login_start_builder(758, "a"), [0x03, 0x00, 0x01, b'a']);
+        assert_eq!(
+            login_start_builder(760, "a"),
+            [0x05, 0x00, 0x01, b'a', 0x00, 0x00]
+        );
+        assert_eq!(login_start_builder(764, "a").len(), 1 + 3 + 16);
+    }
+}
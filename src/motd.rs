@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::server_object::{Component, Description};
+
+const PLACEHOLDER: &str = "{}"; // Stands in for %name%, {name} and obfuscated text
+const NUMBER: &str = "#"; // Stands in for a run of digits, counters and clocks change every ping
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum McColor {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+    Custom(u8, u8, u8),
+}
+
+impl McColor {
+    pub fn from_name(name: &str) -> Option<McColor> {
+        // Accepts the named chat colors and the "#RRGGBB" form 1.16+ servers can send
+        let color = match name {
+            "black" => McColor::Black,
+            "dark_blue" => McColor::DarkBlue,
+            "dark_green" => McColor::DarkGreen,
+            "dark_aqua" => McColor::DarkAqua,
+            "dark_red" => McColor::DarkRed,
+            "dark_purple" => McColor::DarkPurple,
+            "gold" => McColor::Gold,
+            "gray" => McColor::Gray,
+            "dark_gray" => McColor::DarkGray,
+            "blue" => McColor::Blue,
+            "green" => McColor::Green,
+            "aqua" => McColor::Aqua,
+            "red" => McColor::Red,
+            "light_purple" => McColor::LightPurple,
+            "yellow" => McColor::Yellow,
+            "white" => McColor::White,
+            _ => {
+                let hex = name.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+                let rgb = u32::from_str_radix(hex, 16).ok()?;
+                McColor::Custom((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+            }
+        };
+        Some(color)
+    }
+
+    pub fn from_legacy_code(code: char) -> Option<McColor> {
+        // The character following a "§" in legacy formatted text
+        let color = match code.to_ascii_lowercase() {
+            '0' => McColor::Black,
+            '1' => McColor::DarkBlue,
+            '2' => McColor::DarkGreen,
+            '3' => McColor::DarkAqua,
+            '4' => McColor::DarkRed,
+            '5' => McColor::DarkPurple,
+            '6' => McColor::Gold,
+            '7' => McColor::Gray,
+            '8' => McColor::DarkGray,
+            '9' => McColor::Blue,
+            'a' => McColor::Green,
+            'b' => McColor::Aqua,
+            'c' => McColor::Red,
+            'd' => McColor::LightPurple,
+            'e' => McColor::Yellow,
+            'f' => McColor::White,
+            _ => return None,
+        };
+        Some(color)
+    }
+
+    pub fn to_rgb(&self) -> (u8, u8, u8) {
+        // Canonical palette, https://minecraft.fandom.com/wiki/Formatting_codes#Color_codes
+        match *self {
+            McColor::Black => (0x00, 0x00, 0x00),
+            McColor::DarkBlue => (0x00, 0x00, 0xAA),
+            McColor::DarkGreen => (0x00, 0xAA, 0x00),
+            McColor::DarkAqua => (0x00, 0xAA, 0xAA),
+            McColor::DarkRed => (0xAA, 0x00, 0x00),
+            McColor::DarkPurple => (0xAA, 0x00, 0xAA),
+            McColor::Gold => (0xFF, 0xAA, 0x00),
+            McColor::Gray => (0xAA, 0xAA, 0xAA),
+            McColor::DarkGray => (0x55, 0x55, 0x55),
+            McColor::Blue => (0x55, 0x55, 0xFF),
+            McColor::Green => (0x55, 0xFF, 0x55),
+            McColor::Aqua => (0x55, 0xFF, 0xFF),
+            McColor::Red => (0xFF, 0x55, 0x55),
+            McColor::LightPurple => (0xFF, 0x55, 0xFF),
+            McColor::Yellow => (0xFF, 0xFF, 0x55),
+            McColor::White => (0xFF, 0xFF, 0xFF),
+            McColor::Custom(r, g, b) => (r, g, b),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub color: Option<McColor>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underlined: bool,
+    pub strikethrough: bool,
+    pub obfuscated: bool,
+}
+
+impl Description {
+    pub fn spans(&self) -> Vec<Span> {
+        // Translatable components render as their key
+        self.spans_with_translations(&HashMap::new())
+    }
+
+    pub fn spans_with_translations(&self, translations: &HashMap<String, String>) -> Vec<Span> {
+        // Flattens the component tree into styled runs of text, children inherit their parent's style.
+        let root = Span {
+            text: String::new(),
+            color: self.color.as_deref().and_then(McColor::from_name),
+            bold: self.bold.unwrap_or(false),
+            italic: self.italic.unwrap_or(false),
+            underlined: self.underlined.unwrap_or(false),
+            strikethrough: self.strikethrough.unwrap_or(false),
+            obfuscated: self.obfuscated.unwrap_or(false),
+        };
+        let mut flattener = Flattener {
+            spans: vec![],
+            translations,
+        };
+        flattener.content(&self.text, &self.translate, &self.with, &self.extra, &root);
+        flattener.spans
+    }
+
+    pub fn plain_text(&self) -> String {
+        self.spans().into_iter().map(|span| span.text).collect()
+    }
+
+    pub fn first_line(&self) -> String {
+        // For compact server lists, "\r\n" line endings lose their "\r" too
+        let text = self.plain_text();
+        text.lines().next().unwrap_or_default().to_string()
+    }
+
+    pub fn display_width(&self) -> usize {
+        // Terminal columns of the widest line, wide CJK characters take two and combining marks none
+        self.plain_text()
+            .lines()
+            .map(UnicodeWidthStr::width)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn links(&self) -> Vec<(String, String)> {
+        // (action, value) of every clickEvent in the tree, in reading order
+        fn collect(component: &Component, links: &mut Vec<(String, String)>) {
+            if let Some(click_event) = &component.click_event {
+                links.push((click_event.action.clone(), click_event.value.clone()));
+            }
+            for child in component.with.iter().chain(&component.extra) {
+                collect(child, links);
+            }
+        }
+
+        let mut links = vec![];
+        if let Some(click_event) = &self.click_event {
+            links.push((click_event.action.clone(), click_event.value.clone()));
+        }
+        for child in self.with.iter().chain(&self.extra) {
+            collect(child, &mut links);
+        }
+        links
+    }
+
+    pub fn normalize_placeholders(&self) -> String {
+        // Plain text with the parts that tend to change between pings replaced by fixed tokens, so
+        // an animated MOTD compares (and hashes) the same every time
+        let text = self
+            .spans()
+            .into_iter()
+            .map(
+                |span| match span.obfuscated && !span.text.trim().is_empty() {
+                    true => PLACEHOLDER.to_string(), // Often re-randomized server side too
+                    false => span.text,
+                },
+            )
+            .collect::<String>();
+
+        let chars = text.chars().collect::<Vec<_>>();
+        let mut normalized = String::new();
+        let mut index = 0;
+        while index < chars.len() {
+            if let Some(length) = placeholder_length(&chars[index..]) {
+                normalized.push_str(PLACEHOLDER);
+                index += length;
+            } else if chars[index].is_ascii_digit() {
+                normalized.push_str(NUMBER);
+                while chars.get(index).is_some_and(char::is_ascii_digit) {
+                    index += 1;
+                }
+            } else {
+                normalized.push(chars[index]);
+                index += 1;
+            }
+        }
+        normalized
+    }
+}
+
+fn placeholder_length(chars: &[char]) -> Option<usize> {
+    // "%player_name%" or "{online}", an identifier between the delimiters and nothing else
+    let close = match chars.first()? {
+        '%' => '%',
+        '{' => '}',
+        _ => return None,
+    };
+    let end = chars[1..].iter().position(|c| *c == close)? + 1;
+    let name = &chars[1..end];
+    let is_identifier = !name.is_empty()
+        && name
+            .iter()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ':'));
+    is_identifier.then_some(end + 1)
+}
+
+struct Flattener<'a> {
+    spans: Vec<Span>,
+    translations: &'a HashMap<String, String>,
+}
+
+impl Flattener<'_> {
+    fn component(&mut self, component: &Component, parent: &Span) {
+        let style = Span {
+            text: String::new(),
+            color: match &component.color {
+                Some(name) => McColor::from_name(name).or(parent.color),
+                None => parent.color,
+            },
+            bold: component.bold.unwrap_or(parent.bold),
+            italic: component.italic.unwrap_or(parent.italic),
+            underlined: component.underlined.unwrap_or(parent.underlined),
+            strikethrough: component.strikethrough.unwrap_or(parent.strikethrough),
+            obfuscated: component.obfuscated.unwrap_or(parent.obfuscated),
+        };
+
+        self.content(
+            &component.text,
+            &component.translate,
+            &component.with,
+            &component.extra,
+            &style,
+        );
+    }
+
+    fn content(
+        &mut self,
+        text: &str,
+        translate: &Option<String>,
+        with: &[Component],
+        extra: &[Component],
+        style: &Span,
+    ) {
+        match translate {
+            Some(key) => self.translated(key, with, style),
+            None => push_span(&mut self.spans, text, style),
+        }
+        for child in extra {
+            self.component(child, style);
+        }
+    }
+
+    fn translated(&mut self, key: &str, with: &[Component], style: &Span) {
+        // Without a translation the key itself is shown, otherwise "%s" and "%1$s" are filled from "with"
+        let template = match self.translations.get(key) {
+            Some(template) => template,
+            None => return push_span(&mut self.spans, key, style),
+        };
+
+        let mut next_argument = 0;
+        let mut rest = template.as_str();
+        while let Some(index) = rest.find('%') {
+            push_span(&mut self.spans, &rest[..index], style);
+            rest = &rest[index + 1..];
+
+            let digits = rest.chars().take_while(char::is_ascii_digit).count();
+            let argument = if rest.starts_with('s') {
+                rest = &rest[1..];
+                next_argument += 1;
+                Some(next_argument - 1)
+            } else if digits > 0 && rest[digits..].starts_with("$s") {
+                let position = rest[..digits].parse::<usize>().unwrap_or(0);
+                rest = &rest[digits + 2..];
+                position.checked_sub(1)
+            } else if let Some(escaped) = rest.strip_prefix('%') {
+                push_span(&mut self.spans, "%", style);
+                rest = escaped;
+                None
+            } else {
+                push_span(&mut self.spans, "%", style);
+                None
+            };
+
+            if let Some(argument) = argument.and_then(|argument| with.get(argument)) {
+                self.component(argument, style);
+            }
+        }
+        push_span(&mut self.spans, rest, style);
+    }
+}
+
+fn push_span(spans: &mut Vec<Span>, text: &str, style: &Span) {
+    // Many servers put legacy "§" codes straight into text, split those into their own styled spans.
+    let mut current = Span {
+        text: String::new(),
+        ..style.clone()
+    };
+    let mut chars = text.chars();
+
+    while let Some(char) = chars.next() {
+        if char != '§' {
+            current.text.push(char);
+            continue;
+        }
+        let code = match chars.next() {
+            Some(code) => code.to_ascii_lowercase(),
+            None => break,
+        };
+
+        let mut next = Span {
+            text: String::new(),
+            ..current.clone()
+        };
+        if let Some(color) = McColor::from_legacy_code(code) {
+            // A color code also clears any formatting before it
+            next.color = Some(color);
+            next.bold = false;
+            next.italic = false;
+            next.underlined = false;
+            next.strikethrough = false;
+            next.obfuscated = false;
+        } else {
+            match code {
+                'k' => next.obfuscated = true,
+                'l' => next.bold = true,
+                'm' => next.strikethrough = true,
+                'n' => next.underlined = true,
+                'o' => next.italic = true,
+                'r' => {
+                    next = Span {
+                        text: String::new(),
+                        ..style.clone()
+                    }
+                }
+                _ => continue, // Unknown codes are dropped
+            }
+        }
+
+        if !current.text.is_empty() {
+            spans.push(current);
+        }
+        current = next;
+    }
+
+    if !current.text.is_empty() {
+        spans.push(current);
+    }
+}
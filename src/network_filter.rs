@@ -0,0 +1,102 @@
+use std::net::IpAddr;
+
+use crate::QueryError;
+
+// Special purpose ranges (RFC 6890 and friends), none of them should ever be scanned
+const RESERVED: [&str; 21] = [
+    "0.0.0.0/8",
+    "10.0.0.0/8",
+    "100.64.0.0/10",
+    "127.0.0.0/8",
+    "169.254.0.0/16",
+    "172.16.0.0/12",
+    "192.0.0.0/24",
+    "192.0.2.0/24",
+    "192.168.0.0/16",
+    "198.18.0.0/15",
+    "198.51.100.0/24",
+    "203.0.113.0/24",
+    "224.0.0.0/4",
+    "240.0.0.0/4",
+    "::/128",
+    "::1/128",
+    "64:ff9b:1::/48",
+    "2001:db8::/32",
+    "fc00::/7",
+    "fe80::/10",
+    "ff00::/8",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    pub fn parse(cidr: &str) -> Result<Self, QueryError> {
+        // "10.0.0.0/8", "2001:db8::/32", or a bare address for a single host
+        let invalid = || QueryError::InvalidAddress {
+            reason: format!("invalid network \"{}\"", cidr),
+        };
+        let (network, prefix) = match cidr.trim().split_once('/') {
+            Some((network, prefix)) => (network, Some(prefix)),
+            None => (cidr.trim(), None),
+        };
+        let network: IpAddr = network.parse().map_err(|_| invalid())?;
+        let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse().map_err(|_| invalid())?,
+            None => max_prefix,
+        };
+        if prefix > max_prefix {
+            return Err(invalid());
+        }
+        Ok(Cidr { network, prefix })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        // An IPv4-mapped IPv6 address is matched as the IPv4 address it carries
+        let (network, ip, bits) = match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                (u32::from(network) as u128, u32::from(ip) as u128, 32)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => (u128::from(network), u128::from(ip), 128),
+            _ => return false,
+        };
+        let shift = bits - self.prefix as u32;
+        network.checked_shr(shift).unwrap_or(0) == ip.checked_shr(shift).unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NetworkFilter {
+    allow: Vec<Cidr>,
+    deny: Vec<Cidr>,
+}
+
+impl NetworkFilter {
+    pub fn allow(mut self, cidr: &str) -> Result<Self, QueryError> {
+        // Once anything is allowed, addresses outside every allowed network are denied
+        self.allow.push(Cidr::parse(cidr)?);
+        Ok(self)
+    }
+
+    pub fn deny(mut self, cidr: &str) -> Result<Self, QueryError> {
+        // Denied networks win over allowed ones
+        self.deny.push(Cidr::parse(cidr)?);
+        Ok(self)
+    }
+
+    pub fn deny_reserved(mut self) -> Self {
+        // Private, loopback, link local, documentation, multicast and the like
+        self.deny
+            .extend(RESERVED.iter().filter_map(|cidr| Cidr::parse(cidr).ok()));
+        self
+    }
+
+    pub fn permits(&self, ip: IpAddr) -> bool {
+        !self.deny.iter().any(|cidr| cidr.contains(ip))
+            && (self.allow.is_empty() || self.allow.iter().any(|cidr| cidr.contains(ip)))
+    }
+}
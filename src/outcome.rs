@@ -0,0 +1,103 @@
+use std::fmt;
+use std::io::prelude::*;
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::ports::StatusPort;
+use crate::socket_options::SocketOptions;
+use crate::{
+    connect, ping_packet_builder, read_pong, read_status_header, resolve, status_packet_builder,
+    validate_address, DEFAULT_PROTOCOL, MAX_PACKET_SIZE,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ScanOutcome(u8);
+
+impl ScanOutcome {
+    pub const REACHABLE: ScanOutcome = ScanOutcome(1); // TCP connect succeeded
+    pub const MINECRAFT: ScanOutcome = ScanOutcome(1 << 1); // Answered with a well formed status header
+    pub const VALID_JSON: ScanOutcome = ScanOutcome(1 << 2);
+    pub const LATENCY: ScanOutcome = ScanOutcome(1 << 3); // Echoed the ping
+
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    pub fn from_bits(bits: u8) -> Self {
+        ScanOutcome(bits & 0x0F) // Unknown bits are dropped
+    }
+
+    pub fn contains(&self, other: ScanOutcome) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    fn insert(&mut self, other: ScanOutcome) {
+        self.0 |= other.0;
+    }
+}
+
+impl fmt::Display for ScanOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = [
+            (ScanOutcome::REACHABLE, "reachable"),
+            (ScanOutcome::MINECRAFT, "minecraft"),
+            (ScanOutcome::VALID_JSON, "valid-json"),
+            (ScanOutcome::LATENCY, "latency"),
+        ];
+        let names = names
+            .iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>();
+        write!(f, "{}", names.join("|"))
+    }
+}
+
+fn classify(stream: &mut TcpStream, hostname: &str, port: u16, outcome: &mut ScanOutcome) {
+    // Each step only runs if the one before it succeeded, the first failure ends the classification
+    if stream
+        .write_all(&status_packet_builder(DEFAULT_PROTOCOL, hostname, port))
+        .is_err()
+    {
+        return;
+    }
+    let header = match read_status_header(stream, Some(MAX_PACKET_SIZE)) {
+        Ok(header) => header,
+        Err(_) => return, // Includes HTTP and TLS answers
+    };
+    outcome.insert(ScanOutcome::MINECRAFT);
+
+    let mut payload = vec![0; header.string_length as usize];
+    if stream.read_exact(&mut payload).is_err() {
+        return;
+    }
+    if serde_json::from_slice::<serde::de::IgnoredAny>(&payload).is_ok() {
+        outcome.insert(ScanOutcome::VALID_JSON);
+    }
+
+    let ping_payload = 0x4D43; // Any value works, it's only checked for being echoed
+    if stream.write_all(&ping_packet_builder(ping_payload)).is_ok()
+        && read_pong(stream, ping_payload).is_ok()
+    {
+        outcome.insert(ScanOutcome::LATENCY);
+    }
+}
+
+pub fn scan_outcome(hostname: &str, port: impl Into<StatusPort>, timeout: Duration) -> ScanOutcome {
+    // First pass for mass scans, never builds a ServerStatus. Invalid or unresolvable hosts are simply unreachable.
+    let StatusPort(port) = port.into();
+    let mut outcome = ScanOutcome::default();
+    let Ok(hostname) = validate_address(hostname, port) else {
+        return outcome;
+    };
+    let Ok(socket_addrs) = resolve(&hostname, port) else {
+        return outcome;
+    };
+    let Ok(mut stream) = connect(&socket_addrs, timeout, &SocketOptions::default()) else {
+        return outcome;
+    };
+    outcome.insert(ScanOutcome::REACHABLE);
+
+    classify(&mut stream, &hostname, port, &mut outcome);
+    outcome
+}
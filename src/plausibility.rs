@@ -0,0 +1,58 @@
+use crate::server_object::ServerStatus;
+
+const ABSURD_MAX: i64 = 1_000_000; // Far beyond the biggest real networks
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plausibility {
+    pub score: u8, // 100 is entirely plausible, every signal takes points off
+    pub reasons: Vec<String>,
+}
+
+impl Plausibility {
+    fn flag(&mut self, penalty: u8, reason: &str) {
+        self.score = self.score.saturating_sub(penalty);
+        self.reasons.push(reason.to_string());
+    }
+}
+
+impl ServerStatus {
+    pub fn plausibility(&self) -> Plausibility {
+        // Heuristic, for filtering fake servers out of scans. Callers pick their own threshold.
+        let mut plausibility = Plausibility {
+            score: 100,
+            reasons: vec![],
+        };
+        let players = &self.players;
+
+        if players.online < 0 || players.max < 0 {
+            plausibility.flag(40, "negative player count");
+        }
+        if players.max > ABSURD_MAX {
+            plausibility.flag(40, "max players is absurdly large");
+        }
+        // Going a little over max is common (slots reserved for staff, fake "full" counts), this isn't
+        if players.online > players.max.saturating_mul(2).saturating_add(100) {
+            plausibility.flag(30, "far more players online than the max");
+        }
+
+        if !self.version.name.chars().any(|char| char.is_ascii_digit())
+            && self.version.protocol <= 0
+        {
+            plausibility.flag(20, "version has neither a version number nor a protocol");
+        }
+
+        let real_players = players.real_players();
+        if real_players.len() > 1
+            && real_players
+                .iter()
+                .all(|sample| sample.id.eq_ignore_ascii_case(&real_players[0].id))
+        {
+            plausibility.flag(30, "every player in the sample has the same UUID");
+        }
+        if real_players.len() as i64 > players.online.max(0) {
+            plausibility.flag(10, "more players in the sample than online");
+        }
+
+        plausibility
+    }
+}
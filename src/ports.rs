@@ -0,0 +1,49 @@
+use crate::{DEFAULT_BEDROCK_PORT, DEFAULT_PORT, DEFAULT_QUERY_PORT};
+
+// Java status (TCP), UDP query and Bedrock ports are easy to mix up as bare u16s, these keep them apart.
+// Plain numbers still convert, so StatusQuery::new("host", 25565) keeps working.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StatusPort(pub u16);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct QueryPort(pub u16);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BedrockPort(pub u16);
+
+impl Default for StatusPort {
+    fn default() -> Self {
+        StatusPort(DEFAULT_PORT)
+    }
+}
+
+impl Default for QueryPort {
+    fn default() -> Self {
+        QueryPort(DEFAULT_QUERY_PORT)
+    }
+}
+
+impl Default for BedrockPort {
+    fn default() -> Self {
+        BedrockPort(DEFAULT_BEDROCK_PORT)
+    }
+}
+
+impl From<u16> for StatusPort {
+    fn from(port: u16) -> Self {
+        StatusPort(port)
+    }
+}
+
+impl From<u16> for QueryPort {
+    fn from(port: u16) -> Self {
+        QueryPort(port)
+    }
+}
+
+impl From<u16> for BedrockPort {
+    fn from(port: u16) -> Self {
+        BedrockPort(port)
+    }
+}
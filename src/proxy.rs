@@ -0,0 +1,289 @@
+use std::error;
+use std::io::{self, prelude::*};
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::connect;
+use crate::socket_options::SocketOptions;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Proxy {
+    // addr is the proxy's "host:port", auth an optional username and password
+    Socks5 {
+        addr: String,
+        auth: Option<(String, String)>,
+    },
+    HttpConnect {
+        addr: String,
+        auth: Option<(String, String)>,
+    },
+}
+
+pub(crate) fn connect_via(
+    proxy: &Proxy,
+    hostname: &str,
+    port: u16,
+    timeout: Duration,
+    options: &SocketOptions,
+) -> Result<TcpStream, Box<dyn error::Error + Send + Sync>> {
+    // Opens a tunnel to hostname:port through the proxy, the proxy resolves the hostname.
+    // Resolving, connecting and the negotiation together have to finish within timeout, the query
+    // then gets its own timeouts back.
+    let deadline = Instant::now() + timeout;
+    let (addr, auth) = match proxy {
+        Proxy::Socks5 { addr, auth } | Proxy::HttpConnect { addr, auth } => (addr, auth),
+    };
+    let socket_addrs = resolve_proxy(addr, timeout)?;
+    let mut stream = connect(&socket_addrs, remaining(deadline)?, options)?;
+
+    let mut negotiation = Negotiation {
+        stream: &mut stream,
+        deadline,
+    };
+    match proxy {
+        Proxy::Socks5 { .. } => socks5_handshake(&mut negotiation, auth, hostname, port)?,
+        Proxy::HttpConnect { .. } => http_connect(&mut negotiation, auth, hostname, port)?,
+    }
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    Ok(stream)
+}
+
+fn resolve_proxy(addr: &str, timeout: Duration) -> io::Result<Vec<SocketAddr>> {
+    // IP literals need no lookup, anything else resolves on its own thread since to_socket_addrs
+    // can't be given a timeout. A lookup that hangs is left behind.
+    if let Ok(socket_addr) = addr.parse::<SocketAddr>() {
+        return Ok(vec![socket_addr]);
+    }
+    let (sender, receiver) = mpsc::channel();
+    let lookup = addr.to_string();
+    thread::spawn(move || {
+        let _ = sender.send(lookup.to_socket_addrs().map(Iterator::collect));
+    });
+    receiver
+        .recv_timeout(timeout)
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "Resolving the proxy timed out"))?
+}
+
+fn remaining(deadline: Instant) -> io::Result<Duration> {
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() {
+        return Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "Proxy negotiation timed out",
+        ));
+    }
+    Ok(remaining)
+}
+
+struct Negotiation<'a> {
+    // Each read and write only gets what is left until the deadline, so a proxy answering a byte at
+    // a time can't stretch the negotiation past it
+    stream: &'a mut TcpStream,
+    deadline: Instant,
+}
+
+impl Negotiation<'_> {
+    fn remaining(&self) -> io::Result<Duration> {
+        remaining(self.deadline)
+    }
+}
+
+impl Read for Negotiation<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.stream.set_read_timeout(Some(self.remaining()?))?;
+        self.stream.read(buffer)
+    }
+}
+
+impl Write for Negotiation<'_> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.stream.set_write_timeout(Some(self.remaining()?))?;
+        self.stream.write(buffer)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+fn socks5_handshake(
+    stream: &mut (impl Read + Write),
+    auth: &Option<(String, String)>,
+    hostname: &str,
+    port: u16,
+) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+    // https://datatracker.ietf.org/doc/html/rfc1928
+    let method = if auth.is_some() { 0x02 } else { 0x00 }; // Username/password or no authentication
+    stream.write_all(&[0x05, 0x01, method])?;
+
+    let mut reply = [0; 2];
+    stream.read_exact(&mut reply)?;
+    if reply != [0x05, method] {
+        return Err("SOCKS5 proxy refused the authentication method".into());
+    }
+
+    if let Some((username, password)) = auth {
+        // https://datatracker.ietf.org/doc/html/rfc1929
+        if username.len() > 255 || password.len() > 255 {
+            return Err("SOCKS5 credentials are too long".into());
+        }
+        let mut request = vec![0x01, username.len() as u8];
+        request.extend(username.as_bytes());
+        request.push(password.len() as u8);
+        request.extend(password.as_bytes());
+        stream.write_all(&request)?;
+
+        stream.read_exact(&mut reply)?;
+        if reply[1] != 0x00 {
+            return Err("SOCKS5 proxy rejected the credentials".into());
+        }
+    }
+
+    let mut request = vec![0x05, 0x01, 0x00]; // CONNECT
+    match hostname.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(0x01);
+            request.extend(ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(0x04);
+            request.extend(ip.octets());
+        }
+        Err(_) => {
+            if hostname.len() > 255 {
+                return Err("Hostname is too long for SOCKS5".into());
+            }
+            request.push(0x03);
+            request.push(hostname.len() as u8);
+            request.extend(hostname.as_bytes());
+        }
+    }
+    request.extend(port.to_be_bytes());
+    stream.write_all(&request)?;
+
+    let mut reply = [0; 4];
+    stream.read_exact(&mut reply)?;
+    if reply[1] != 0x00 {
+        return Err(format!("SOCKS5 proxy failed to connect (reply {:#04x})", reply[1]).into());
+    }
+
+    // Skip the bound address, its length depends on the address type
+    let address_length = match reply[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => {
+            let mut length = [0];
+            stream.read_exact(&mut length)?;
+            length[0] as usize
+        }
+        _ => return Err("SOCKS5 proxy replied with an unknown address type".into()),
+    };
+    stream.read_exact(&mut vec![0; address_length + 2])?; // Address and port
+    Ok(())
+}
+
+fn http_connect(
+    stream: &mut (impl Read + Write),
+    auth: &Option<(String, String)>,
+    hostname: &str,
+    port: u16,
+) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+    let target = if hostname.contains(':') {
+        format!("[{}]:{}", hostname, port)
+    } else {
+        format!("{}:{}", hostname, port)
+    };
+
+    let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", target);
+    if let Some((username, password)) = auth {
+        let credentials = base64_encode(format!("{}:{}", username, password).as_bytes());
+        request += &format!("Proxy-Authorization: Basic {}\r\n", credentials);
+    }
+    request += "\r\n";
+    stream.write_all(request.as_bytes())?;
+
+    // Read byte by byte up to the blank line, anything after it belongs to the tunnel
+    let mut response = vec![];
+    let mut byte = [0];
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() > 8 * 1024 {
+            return Err("HTTP proxy response headers are too long".into());
+        }
+        stream.read_exact(&mut byte)?;
+        response.push(byte[0]);
+    }
+
+    let response = String::from_utf8_lossy(&response);
+    let status = response.split_whitespace().nth(1).unwrap_or_default();
+    if status != "200" {
+        let status_line = response.lines().next().unwrap_or_default();
+        return Err(format!("HTTP proxy refused to connect: {}", status_line).into());
+    }
+    Ok(())
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+
+    for chunk in bytes.chunks(3) {
+        let triple = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |triple, (index, byte)| {
+                triple | (*byte as u32) << (16 - index * 8)
+            });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(triple >> (18 - index * 6)) as usize & 0x3F] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64() {
+        assert_eq!(base64_encode(b"user:pass"), "dXNlcjpwYXNz");
+        assert_eq!(base64_encode(b"ab"), "YWI=");
+        assert_eq!(base64_encode(b"a"), "YQ==");
+    }
+
+    #[test]
+    fn deadline() {
+        // Resolving and connecting to the proxy count against the same timeout as the negotiation
+        assert_eq!(
+            resolve_proxy("127.0.0.1:1080", Duration::ZERO).unwrap(),
+            vec![SocketAddr::from(([127, 0, 0, 1], 1080))]
+        );
+        assert!(!resolve_proxy("localhost:1080", Duration::from_secs(5))
+            .unwrap()
+            .is_empty());
+
+        let proxy = Proxy::Socks5 {
+            addr: "127.0.0.1:1080".into(),
+            auth: None,
+        };
+        let error = connect_via(
+            &proxy,
+            "localhost",
+            25565,
+            Duration::ZERO,
+            &SocketOptions::default(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<io::Error>().unwrap().kind(),
+            io::ErrorKind::TimedOut
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use std::net::{IpAddr, SocketAddr};
+
+const V2_SIGNATURE: [u8; 12] = [
+    0x0D, 0x0A, 0x0D, 0x0A, 0x00, 0x0D, 0x0A, 0x51, 0x55, 0x49, 0x54, 0x0A,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyProtocol {
+    // HAProxy PROXY protocol header sent ahead of the handshake, for servers behind load balancers
+    // that expect one. https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt
+    V1 {
+        source: SocketAddr,
+        destination: SocketAddr,
+    },
+    V2 {
+        source: SocketAddr,
+        destination: SocketAddr,
+    },
+}
+
+fn same_family(source: SocketAddr, destination: SocketAddr) -> (SocketAddr, SocketAddr) {
+    // Both addresses have to share a family, a mixed pair is sent as IPv4-mapped IPv6
+    let to_v6 = |addr: SocketAddr| match addr.ip() {
+        IpAddr::V4(ip) => SocketAddr::new(IpAddr::V6(ip.to_ipv6_mapped()), addr.port()),
+        IpAddr::V6(_) => addr,
+    };
+    if source.is_ipv4() == destination.is_ipv4() {
+        (source, destination)
+    } else {
+        (to_v6(source), to_v6(destination))
+    }
+}
+
+impl ProxyProtocol {
+    pub fn header(&self) -> Vec<u8> {
+        match *self {
+            ProxyProtocol::V1 {
+                source,
+                destination,
+            } => {
+                let (source, destination) = same_family(source, destination);
+                let family = if source.is_ipv4() { "TCP4" } else { "TCP6" };
+                format!(
+                    "PROXY {} {} {} {} {}\r\n",
+                    family,
+                    source.ip(),
+                    destination.ip(),
+                    source.port(),
+                    destination.port()
+                )
+                .into_bytes()
+            }
+            ProxyProtocol::V2 {
+                source,
+                destination,
+            } => {
+                let (source, destination) = same_family(source, destination);
+                let (family, addresses) = match (source.ip(), destination.ip()) {
+                    (IpAddr::V4(source), IpAddr::V4(destination)) => {
+                        (0x11, [source.octets(), destination.octets()].concat())
+                    }
+                    (source, destination) => (
+                        0x21,
+                        [ipv6_octets(source), ipv6_octets(destination)].concat(),
+                    ),
+                };
+                let payload = [
+                    addresses,
+                    source.port().to_be_bytes().to_vec(),
+                    destination.port().to_be_bytes().to_vec(),
+                ]
+                .concat();
+                [
+                    V2_SIGNATURE.to_vec(),
+                    vec![0x21, family], // Version 2 PROXY command, then TCP over IPv4 (0x11) or IPv6 (0x21)
+                    (payload.len() as u16).to_be_bytes().to_vec(),
+                    payload,
+                ]
+                .concat()
+            }
+        }
+    }
+}
+
+fn ipv6_octets(ip: IpAddr) -> [u8; 16] {
+    match ip {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped().octets(),
+        IpAddr::V6(ip) => ip.octets(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers() {
+        let source: SocketAddr = "192.0.2.1:50000".parse().unwrap();
+        let destination: SocketAddr = "198.51.100.2:25565".parse().unwrap();
+        assert_eq!(
+            ProxyProtocol::V1 {
+                source,
+                destination
+            }
+            .header(),
+            b"PROXY TCP4 192.0.2.1 198.51.100.2 50000 25565\r\n"
+        );
+
+        let header = ProxyProtocol::V2 {
+            source,
+            destination,
+        }
+        .header();
+        assert_eq!(header[..12], V2_SIGNATURE);
+        assert_eq!(header[12..16], [0x21, 0x11, 0x00, 12]);
+        assert_eq!(header[16..20], [192, 0, 2, 1]);
+        assert_eq!(header[24..28], [0xC3, 0x50, 0x63, 0xDD]); // Ports 50000 and 25565
+
+        let mixed = ProxyProtocol::V2 {
+            source: "[2001:db8::1]:50000".parse().unwrap(),
+            destination,
+        }
+        .header();
+        assert_eq!(mixed[13], 0x21);
+        assert_eq!(mixed.len(), 16 + 36);
+    }
+}
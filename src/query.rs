@@ -0,0 +1,366 @@
+use std::error;
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::clock::{Clock, SystemClock};
+use crate::early::EarlyStatus;
+use crate::handshake::HandshakeStyle;
+use crate::ports::StatusPort;
+use crate::proxy::Proxy;
+use crate::proxy_protocol::ProxyProtocol;
+use crate::query_error::QueryError;
+use crate::server_object::ServerStatus;
+use crate::socket_options::SocketOptions;
+use crate::{
+    build_handshake, status_request_builder, FullStatus, RawStatusFrame, Timings, TrailingData,
+    DEFAULT_PROTOCOL, MAX_PACKET_SIZE, MAX_SAMPLE_PLAYERS, TIMEOUT,
+};
+
+pub type RedirectCallback = dyn Fn(&serde_json::Value) -> Option<(String, u16)> + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct Redirect {
+    pub(crate) callback: Arc<RedirectCallback>,
+    pub(crate) max_redirects: usize,
+}
+
+#[derive(Clone)]
+pub struct StatusQuery {
+    pub(crate) hostname: String,
+    pub(crate) port: u16,
+    pub(crate) protocol: i32,
+    pub(crate) handshake_hostname: Option<String>,
+    pub(crate) handshake_port: Option<u16>,
+    pub(crate) extra_handshake_fields: Vec<String>,
+    pub(crate) timeout: Duration,
+    pub(crate) socket_addrs: Option<Vec<SocketAddr>>,
+    pub(crate) redirect: Option<Redirect>,
+    pub(crate) proxy: Option<Proxy>,
+    pub(crate) proxy_protocol: Option<ProxyProtocol>,
+    pub(crate) lossy_utf8: bool,
+    pub(crate) split_writes: bool,
+    pub(crate) capture: bool,
+    pub(crate) socket_options: SocketOptions,
+    pub(crate) max_packet_size: Option<u32>,
+    pub(crate) max_sample_players: usize,
+    pub(crate) json_end: Option<TrailingData>,
+    pub(crate) max_read_calls: Option<usize>,
+    pub(crate) clock: Arc<dyn Clock>,
+}
+
+impl StatusQuery {
+    pub fn new(hostname: &str, port: impl Into<StatusPort>) -> Self {
+        StatusQuery {
+            hostname: hostname.to_string(),
+            port: port.into().0,
+            protocol: DEFAULT_PROTOCOL,
+            handshake_hostname: None,
+            handshake_port: None,
+            extra_handshake_fields: vec![],
+            timeout: TIMEOUT,
+            socket_addrs: None,
+            redirect: None,
+            proxy: None,
+            proxy_protocol: None,
+            lossy_utf8: false,
+            split_writes: false,
+            capture: false,
+            socket_options: SocketOptions::default(),
+            max_packet_size: Some(MAX_PACKET_SIZE),
+            max_sample_players: MAX_SAMPLE_PLAYERS,
+            json_end: None,
+            max_read_calls: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    pub fn protocol_version(mut self, protocol: i32) -> Self {
+        // Protocol sent in the handshake, defaults to -1 which means "unknown, just pinging"
+        self.protocol = protocol;
+        self
+    }
+
+    pub fn handshake_hostname(mut self, hostname: &str) -> Self {
+        // Hostname sent in the handshake instead of the one we connect to, proxies route virtual
+        // hosts on it. Sent as given, it isn't validated or resolved.
+        self.handshake_hostname = Some(hostname.to_string());
+        self
+    }
+
+    pub fn handshake_port(mut self, port: u16) -> Self {
+        // Port encoded in the handshake, some proxies expect a different one than we connect to.
+        // Defaults to the requested port, which is what vanilla clients send.
+        self.handshake_port = Some(port);
+        self
+    }
+
+    pub fn extra_handshake_fields(mut self, fields: Vec<String>) -> Self {
+        // Appended to the handshake hostname separated by "\0", the way BungeeCord IP forwarding
+        // ("<ip>", "<uuid>") and Geyser ("<ip>", "<uuid>", "<xuid>") do it.
+        self.extra_handshake_fields = fields;
+        self
+    }
+
+    pub fn handshake_style(self, style: HandshakeStyle) -> Self {
+        // Presets for the handshake hostname behind proxies, replaces any extra_handshake_fields
+        self.extra_handshake_fields(style.fields())
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        // Used for both connecting and each read, defaults to 5 seconds
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        // Tunnels the query through a SOCKS5 or HTTP CONNECT proxy
+        self.proxy = Some(proxy);
+        self
+    }
+
+    pub fn proxy_protocol(mut self, header: ProxyProtocol) -> Self {
+        // Sent before the handshake, for servers behind a load balancer that expects a PROXY header
+        self.proxy_protocol = Some(header);
+        self
+    }
+
+    pub fn lossy_utf8(mut self) -> Self {
+        // Replaces invalid UTF-8 in the payload instead of failing, one bad byte shouldn't lose the whole status
+        self.lossy_utf8 = true;
+        self
+    }
+
+    pub fn split_writes(mut self) -> Self {
+        // Writes and flushes the handshake before the status request, for implementations that can't
+        // handle both arriving in one segment. Off by default, one write is cheaper.
+        self.split_writes = true;
+        self
+    }
+
+    pub fn capture(mut self) -> Self {
+        // Records every byte sent and received into FullStatus::transcript, for bug reports and
+        // building replay fixtures. Costs a copy of the whole response, so off by default.
+        self.capture = true;
+        self
+    }
+
+    pub fn recv_buffer_size(mut self, size: usize) -> Self {
+        // SO_RCVBUF, a larger buffer means fewer reads for huge modded responses
+        self.socket_options.recv_buffer_size = Some(size);
+        self
+    }
+
+    pub fn send_buffer_size(mut self, size: usize) -> Self {
+        // SO_SNDBUF
+        self.socket_options.send_buffer_size = Some(size);
+        self
+    }
+
+    pub fn ttl(mut self, ttl: u32) -> Self {
+        // IP TTL (hop limit on IPv6) of outgoing packets, for path diagnostics. OS default when unset.
+        self.socket_options.ttl = Some(ttl);
+        self
+    }
+
+    pub fn source_port(mut self, port: u16) -> Self {
+        // Binds the local port before connecting, for egress rules that pin it. A port that's taken
+        // fails with QueryError::SourcePortInUse. Ignored by a custom connector.
+        self.socket_options.source_port = Some(port);
+        self
+    }
+
+    pub fn connector(
+        mut self,
+        connector: impl Fn(&SocketAddr, Duration) -> io::Result<TcpStream> + Send + Sync + 'static,
+    ) -> Self {
+        // Replaces the built in connect for each resolved address (or the proxy), e.g. to add
+        // instrumentation or hand back a mock transport. Blocking API only.
+        self.socket_options.connector = Some(Arc::new(connector));
+        self
+    }
+
+    pub fn max_packet_size(mut self, max_packet_size: Option<u32>) -> Self {
+        // Largest status string accepted, defaults to 50MB. None removes the limit.
+        self.max_packet_size = max_packet_size;
+        self
+    }
+
+    pub fn no_packet_limit(self) -> Self {
+        // For trusted servers whose legitimate (usually modded) status doesn't fit under any cap
+        self.max_packet_size(None)
+    }
+
+    pub fn max_sample_players(mut self, max_sample_players: usize) -> Self {
+        // Sample entries kept when parsing, defaults to 1000. Anything past it is skipped, not stored.
+        self.max_sample_players = max_sample_players;
+        self
+    }
+
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        // Time source for ping latency and phase timings, swap in a ManualClock for deterministic tests
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    pub fn max_read_calls(mut self, max_read_calls: usize) -> Self {
+        // Caps the socket reads spent on one payload (the header is not counted), separate from the size
+        // cap. Going over fails with QueryError::TooManyReads. Unlimited by default.
+        self.max_read_calls = Some(max_read_calls);
+        self
+    }
+
+    pub fn stop_at_json_end(mut self, trailing: TrailingData) -> Self {
+        // Stops reading once the first JSON value is complete instead of trusting the announced length,
+        // anything after it is ignored or rejected. The payload has to be valid UTF-8 in this mode.
+        // raw_status_frame refuses it, the frame wouldn't be the one sent anymore.
+        self.json_end = Some(trailing);
+        self
+    }
+
+    pub(crate) fn socket_addrs(mut self, socket_addrs: Vec<SocketAddr>) -> Self {
+        // Skips DNS resolution, for callers that already resolved (and cached) the hostname
+        self.socket_addrs = Some(socket_addrs);
+        self
+    }
+
+    pub fn follow_redirects(
+        mut self,
+        max_redirects: usize,
+        callback: impl Fn(&serde_json::Value) -> Option<(String, u16)> + Send + Sync + 'static,
+    ) -> Self {
+        // Not standard, some lobbies advertise the real server in their status. The callback returns
+        // where to re-query, at most max_redirects times.
+        self.redirect = Some(Redirect {
+            callback: Arc::new(callback),
+            max_redirects,
+        });
+        self
+    }
+
+    pub(crate) fn redirected(&self, hostname: &str, port: u16) -> Self {
+        // Same settings aimed at a new server, anything specific to the old address is dropped
+        StatusQuery {
+            hostname: hostname.to_string(),
+            port,
+            handshake_hostname: None,
+            handshake_port: None,
+            extra_handshake_fields: vec![],
+            socket_addrs: None,
+            proxy_protocol: None,
+            ..self.clone()
+        }
+    }
+
+    fn handshake_packet(&self, hostname: &str) -> Vec<u8> {
+        let hostname = self.handshake_hostname.as_deref().unwrap_or(hostname);
+        let handshake_hostname = [hostname.to_string()]
+            .into_iter()
+            .chain(self.extra_handshake_fields.iter().cloned())
+            .collect::<Vec<_>>()
+            .join("\0");
+        let handshake_port = self.handshake_port.unwrap_or(self.port);
+        let header = self.proxy_protocol.map(|header| header.header());
+        [
+            header.unwrap_or_default(),
+            build_handshake(self.protocol, &handshake_hostname, handshake_port, 0x01),
+        ]
+        .concat()
+    }
+
+    pub(crate) fn status_packet(&self, hostname: &str) -> Vec<u8> {
+        [self.handshake_packet(hostname), status_request_builder()].concat()
+    }
+
+    pub(crate) fn status_packets(&self, hostname: &str) -> Vec<Vec<u8>> {
+        // One write per entry, the handshake only gets its own when split_writes is set
+        if self.split_writes {
+            vec![self.handshake_packet(hostname), status_request_builder()]
+        } else {
+            vec![self.status_packet(hostname)]
+        }
+    }
+
+    pub(crate) fn send_status_request(
+        &self,
+        stream: &mut impl Write,
+        hostname: &str,
+    ) -> io::Result<usize> {
+        // Returns the bytes written
+        let mut sent = 0;
+        for packet in self.status_packets(hostname) {
+            stream.write_all(&packet)?;
+            stream.flush()?;
+            sent += packet.len();
+        }
+        Ok(sent)
+    }
+
+    pub(crate) fn decode(
+        &self,
+        buffer: Vec<u8>,
+    ) -> Result<String, Box<dyn error::Error + Send + Sync>> {
+        if self.lossy_utf8 {
+            return Ok(String::from_utf8_lossy(&buffer).into_owned());
+        }
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    pub fn get_server_json(&self) -> Result<String, Box<dyn error::Error + Send + Sync>> {
+        crate::timed_server_json(self, &mut Timings::default(), &mut None)
+    }
+
+    pub fn get_server_json_pretty(&self) -> Result<String, Box<dyn error::Error + Send + Sync>> {
+        // Indented for eyeballing, get_server_json stays compact for programs
+        let json = crate::timed_server_value(self, &mut Timings::default(), &mut None)?;
+        Ok(serde_json::to_string_pretty(&json)?)
+    }
+
+    pub fn server_status(&self) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+        // Parsed once from the payload, get_server_json is only for callers who want the JSON itself
+        crate::timed_server_status(self, &mut Timings::default(), &mut None)
+    }
+
+    pub fn server_status_streaming(
+        &self,
+    ) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+        crate::streaming_status(self)
+    }
+
+    pub fn early_status(&self) -> Result<EarlyStatus, Box<dyn error::Error + Send + Sync>> {
+        // Returns once version and players are in, without waiting for the favicon. Redirects aren't followed.
+        crate::early::early_status(self)
+    }
+
+    pub fn server_status_into(
+        &self,
+        buffer: &mut Vec<u8>,
+    ) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+        // Reads the payload into buffer, which is cleared first and can be reused across queries
+        crate::buffered_status(self, buffer)
+    }
+
+    pub fn raw_status_frame(&self) -> Result<RawStatusFrame, Box<dyn error::Error + Send + Sync>> {
+        crate::raw_frame(self)
+    }
+
+    pub fn ping(&self) -> Result<Duration, Box<dyn error::Error + Send + Sync>> {
+        // Round trip of a ping sent after the status exchange, the pong is read and checked in full
+        crate::query_ping(self)
+    }
+
+    pub fn full_status(&self) -> Result<FullStatus, Box<dyn error::Error + Send + Sync>> {
+        crate::full_query_status(self)
+    }
+
+    pub fn probe(&self) -> Result<(), QueryError> {
+        // Only checks that a Minecraft server answers, the status payload is never read
+        crate::query_probe(self)
+    }
+
+    pub fn tcp_connect_time(&self) -> Result<Duration, QueryError> {
+        // Time to open the TCP connection, nothing is sent
+        crate::connect_time(self)
+    }
+}
@@ -0,0 +1,138 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::mem;
+use std::net::IpAddr;
+
+#[derive(Debug)]
+pub enum QueryError {
+    InvalidAddress { reason: String },
+    Io(io::Error),
+    InvalidResponse { reason: String },
+    EmptyResponse,
+    FrameLengthMismatch { frame_length: i64, expected: i64 },
+    NotAMinecraftServer { protocol: &'static str },
+    AllAddressesFailed { errors: Vec<io::Error> },
+    InvalidLength { length: i64 },
+    ConnectionRefused,
+    Timeout,
+    Unreachable,
+    TargetDenied { ip: IpAddr },
+    SourcePortInUse { port: u16 },
+    ConnectionClosedEarly,
+    TooManyReads { max_reads: usize },
+}
+
+impl QueryError {
+    pub(crate) fn from_boxed(error: Box<dyn error::Error + Send + Sync>) -> QueryError {
+        // Recovers the typed error from the boxed ones most of the crate returns
+        let error = match error.downcast::<QueryError>() {
+            Ok(error) => return *error,
+            Err(error) => error,
+        };
+        match error.downcast::<io::Error>() {
+            Ok(error) => QueryError::Io(*error),
+            Err(error) => QueryError::InvalidResponse {
+                reason: error.to_string(),
+            },
+        }
+    }
+}
+
+impl QueryError {
+    pub(crate) fn connect_failed(mut errors: Vec<io::Error>) -> QueryError {
+        // When every address failed the same way it gets a typed bucket (port closed, host silent,
+        // no route), so a dual stack host with the port closed is still just refused. Mixed failures
+        // keep the per address errors, a single unexpected one stays an Io error.
+        let mut typed = errors.iter().map(typed_connect_error);
+        if let Some(Some(first)) = typed.next() {
+            let same = |other: Option<QueryError>| {
+                other.is_some_and(|other| mem::discriminant(&other) == mem::discriminant(&first))
+            };
+            if typed.all(same) {
+                return first;
+            }
+        }
+        if errors.len() == 1 {
+            return QueryError::Io(errors.remove(0));
+        }
+        QueryError::AllAddressesFailed { errors }
+    }
+}
+
+fn typed_connect_error(error: &io::Error) -> Option<QueryError> {
+    match error.kind() {
+        io::ErrorKind::ConnectionRefused => Some(QueryError::ConnectionRefused),
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => Some(QueryError::Timeout),
+        io::ErrorKind::HostUnreachable | io::ErrorKind::NetworkUnreachable => {
+            Some(QueryError::Unreachable)
+        }
+        _ => None,
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryError::InvalidAddress { reason } => write!(f, "Invalid address: {}", reason),
+            QueryError::Io(error) => write!(f, "{}", error),
+            QueryError::InvalidResponse { reason } => write!(f, "Invalid response: {}", reason),
+            QueryError::EmptyResponse => write!(f, "Server sent an empty status response"),
+            QueryError::FrameLengthMismatch {
+                frame_length,
+                expected,
+            } => write!(
+                f,
+                "Packet length {} doesn't match its contents, expected {}",
+                frame_length, expected
+            ),
+            QueryError::NotAMinecraftServer { protocol } => {
+                write!(
+                    f,
+                    "Not a Minecraft server, the port answered with {}",
+                    protocol
+                )
+            }
+            QueryError::AllAddressesFailed { errors } => {
+                let errors = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+                write!(f, "Couldn't connect to any address: {}", errors.join("; "))
+            }
+            QueryError::InvalidLength { length } => {
+                write!(f, "Response announced an invalid length of {}", length)
+            }
+            QueryError::ConnectionRefused => write!(f, "Connection refused, the port is closed"),
+            QueryError::Timeout => write!(f, "Timed out connecting, the host didn't answer"),
+            QueryError::Unreachable => write!(f, "Host unreachable"),
+            QueryError::TargetDenied { ip } => {
+                write!(f, "Not connecting to {}, denied by the network filter", ip)
+            }
+            QueryError::SourcePortInUse { port } => {
+                write!(f, "Source port {} is already in use", port)
+            }
+            QueryError::ConnectionClosedEarly => {
+                write!(f, "Server closed the connection without responding")
+            }
+            QueryError::TooManyReads { max_reads } => {
+                write!(f, "Response took more than {} reads", max_reads)
+            }
+        }
+    }
+}
+
+impl error::Error for QueryError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            QueryError::Io(error) => Some(error),
+            QueryError::AllAddressesFailed { errors } => errors
+                .first()
+                .map(|error| error as &(dyn error::Error + 'static)),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for QueryError {
+    fn from(error: io::Error) -> Self {
+        QueryError::Io(error)
+    }
+}
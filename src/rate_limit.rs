@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::clock::{Clock, SystemClock};
+
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    updated: Option<Instant>, // None until the first reservation, the bucket starts full
+}
+
+impl Bucket {
+    fn new(rate: f64) -> Self {
+        Bucket {
+            rate,
+            tokens: 1.0, // No bursts, queries are spaced evenly
+            updated: None,
+        }
+    }
+
+    fn reserve(&mut self, now: Instant) -> Duration {
+        // Takes a token now, going negative if needed, and returns how long to wait for it
+        if let Some(updated) = self.updated {
+            let elapsed = now.duration_since(updated).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * self.rate).min(1.0);
+        }
+        self.updated = Some(now);
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+
+    fn is_full(&self, now: Instant) -> bool {
+        // Refilled buckets behave exactly like new ones
+        let elapsed = self
+            .updated
+            .map_or(0.0, |updated| now.duration_since(updated).as_secs_f64());
+        self.tokens + elapsed * self.rate >= 1.0
+    }
+}
+
+const MIN_PRUNE_AT: usize = 1024;
+
+struct PerHost {
+    rate: f64,
+    buckets: HashMap<IpAddr, Bucket>,
+    prune_at: usize,
+}
+
+impl PerHost {
+    fn reserve(&mut self, host: IpAddr, now: Instant) -> Duration {
+        // A wide scan touches every host once, so refilled buckets are dropped instead of kept forever.
+        // The sweep only runs once the map has doubled since the last one.
+        if self.buckets.len() >= self.prune_at {
+            self.buckets.retain(|_, bucket| !bucket.is_full(now));
+            self.prune_at = (self.buckets.len() * 2).max(MIN_PRUNE_AT);
+        }
+        let rate = self.rate;
+        self.buckets
+            .entry(host)
+            .or_insert_with(|| Bucket::new(rate))
+            .reserve(now)
+    }
+}
+
+pub struct RateLimiter {
+    global: Mutex<Bucket>,
+    per_host: Option<Mutex<PerHost>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl RateLimiter {
+    pub fn new(queries_per_second: f64) -> Self {
+        assert!(queries_per_second > 0.0, "Rate must be positive");
+        RateLimiter {
+            global: Mutex::new(Bucket::new(queries_per_second)),
+            per_host: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    pub fn per_host(mut self, queries_per_second: f64) -> Self {
+        assert!(queries_per_second > 0.0, "Rate must be positive");
+        self.per_host = Some(Mutex::new(PerHost {
+            rate: queries_per_second,
+            buckets: HashMap::new(),
+            prune_at: MIN_PRUNE_AT,
+        }));
+        self
+    }
+
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        // Time source for the buckets and the waits, a ManualClock makes a limited scan instant in tests
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    pub(crate) fn wait(&self, host: IpAddr) {
+        self.clock.sleep(self.reserve(host)); // Sleep outside the locks so other hosts aren't held up
+    }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn wait_async(&self, host: IpAddr) {
+        self.clock.sleep_async(self.reserve(host)).await;
+    }
+
+    pub(crate) fn reserve(&self, host: IpAddr) -> Duration {
+        // Claims a slot for host and returns how long until it may be used
+        let now = self.clock.now();
+        let mut delay = self.global.lock().unwrap().reserve(now);
+        if let Some(per_host) = &self.per_host {
+            delay = delay.max(per_host.lock().unwrap().reserve(host, now));
+        }
+        delay
+    }
+
+    pub fn tracked_hosts(&self) -> usize {
+        // Hosts currently holding a per-host bucket, refilled ones are pruned as the map grows
+        self.per_host
+            .as_ref()
+            .map_or(0, |per_host| per_host.lock().unwrap().buckets.len())
+    }
+}
@@ -0,0 +1,155 @@
+use crate::motd::Span;
+use crate::server_object::Description;
+
+pub trait MotdRenderer {
+    fn render(&self, spans: &[Span]) -> String;
+}
+
+pub struct PlainText;
+
+pub struct Ansi;
+
+pub struct Html;
+
+pub struct Markdown;
+
+impl MotdRenderer for PlainText {
+    fn render(&self, spans: &[Span]) -> String {
+        spans.iter().map(|span| span.text.as_str()).collect()
+    }
+}
+
+impl MotdRenderer for Ansi {
+    fn render(&self, spans: &[Span]) -> String {
+        // 24-bit color escapes, each span resets so styles never leak into the next
+        let mut rendered = String::new();
+        for span in spans {
+            let mut codes = vec![];
+            if let Some(color) = span.color {
+                let (r, g, b) = color.to_rgb();
+                codes.push(format!("38;2;{};{};{}", r, g, b));
+            }
+            for (enabled, code) in [
+                (span.bold, "1"),
+                (span.italic, "3"),
+                (span.underlined, "4"),
+                (span.strikethrough, "9"),
+            ] {
+                if enabled {
+                    codes.push(code.to_string());
+                }
+            }
+
+            if codes.is_empty() {
+                rendered.push_str(&span.text);
+            } else {
+                rendered.push_str(&format!("\x1b[{}m{}\x1b[0m", codes.join(";"), span.text));
+            }
+        }
+        rendered
+    }
+}
+
+impl MotdRenderer for Html {
+    fn render(&self, spans: &[Span]) -> String {
+        let mut rendered = String::new();
+        for span in spans {
+            let mut styles = vec![];
+            if let Some(color) = span.color {
+                let (r, g, b) = color.to_rgb();
+                styles.push(format!("color:#{:02x}{:02x}{:02x}", r, g, b));
+            }
+            if span.bold {
+                styles.push("font-weight:bold".into());
+            }
+            if span.italic {
+                styles.push("font-style:italic".into());
+            }
+            match (span.underlined, span.strikethrough) {
+                (true, true) => styles.push("text-decoration:underline line-through".into()),
+                (true, false) => styles.push("text-decoration:underline".into()),
+                (false, true) => styles.push("text-decoration:line-through".into()),
+                (false, false) => {}
+            }
+
+            let text = escape_html(&span.text).replace('\n', "<br>");
+            if styles.is_empty() {
+                rendered.push_str(&text);
+            } else {
+                rendered.push_str(&format!(
+                    "<span style=\"{}\">{}</span>",
+                    styles.join(";"),
+                    text
+                ));
+            }
+        }
+        rendered
+    }
+}
+
+impl MotdRenderer for Markdown {
+    fn render(&self, spans: &[Span]) -> String {
+        // Markdown has no colors or underline, only bold, italic and strikethrough survive.
+        // Markers go around each line's trimmed text, "** x**" wouldn't be bold.
+        let mut rendered = String::new();
+        for span in spans {
+            let marker = [
+                (span.bold, "**"),
+                (span.italic, "*"),
+                (span.strikethrough, "~~"),
+            ]
+            .iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, marker)| *marker)
+            .collect::<String>();
+
+            let lines: Vec<String> = span
+                .text
+                .split('\n')
+                .map(|line| {
+                    let trimmed = line.trim();
+                    if marker.is_empty() || trimmed.is_empty() {
+                        return escape_markdown(line);
+                    }
+                    let start = line.len() - line.trim_start().len();
+                    let end = line.trim_end().len();
+                    let closing: String = marker.chars().rev().collect();
+                    format!(
+                        "{}{}{}{}{}",
+                        &line[..start],
+                        marker,
+                        escape_markdown(trimmed),
+                        closing,
+                        &line[end..]
+                    )
+                })
+                .collect();
+            rendered.push_str(&lines.join("\n"));
+        }
+        rendered
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::new();
+    for char in text.chars() {
+        if "\\`*_~[]()#<>|".contains(char) {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+    escaped
+}
+
+impl Description {
+    pub fn render_with(&self, renderer: &impl MotdRenderer) -> String {
+        renderer.render(&self.spans())
+    }
+}
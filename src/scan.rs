@@ -0,0 +1,260 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::error;
+use std::hash::{BuildHasher, Hasher};
+use std::net::SocketAddr;
+use std::ops::Range;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::clock::{Clock, SystemClock};
+use crate::network_filter::NetworkFilter;
+use crate::rate_limit::RateLimiter;
+use crate::server_object::ServerStatus;
+use crate::socket_options::SocketOptions;
+use crate::{resolve, split_address, validate_address, QueryError, StatusQuery, TIMEOUT};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Target {
+    pub hostname: String,
+    pub port: u16,
+}
+
+impl Target {
+    pub fn new(hostname: &str, port: u16) -> Self {
+        Target {
+            hostname: hostname.to_string(),
+            port,
+        }
+    }
+
+    pub fn parse(address: &str) -> Result<Self, QueryError> {
+        // "host", "host:port" or "[ipv6]:port", the port defaults to 25565
+        let (hostname, port) = split_address(address)?;
+        Ok(Target::new(hostname, port))
+    }
+}
+
+pub type ScanResult = (
+    Target,
+    Result<ServerStatus, Box<dyn error::Error + Send + Sync>>,
+);
+
+pub struct Pacing {
+    pub rate_limiter: Option<RateLimiter>,
+    pub jitter: Option<Range<Duration>>, // Random extra delay before each connect, so a sweep isn't uniform
+    pub clock: Arc<dyn Clock>,           // Sleeps the jitter, the rate limiter has its own
+}
+
+impl Default for Pacing {
+    fn default() -> Self {
+        Pacing {
+            rate_limiter: None,
+            jitter: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+pub type TypedScanResult = (Target, Result<ServerStatus, QueryError>);
+
+enum Reply {
+    Boxed(Sender<ScanResult>),
+    Typed(Sender<TypedScanResult>),
+}
+
+impl Reply {
+    fn send(
+        &self,
+        target: Target,
+        status: Result<ServerStatus, Box<dyn error::Error + Send + Sync>>,
+    ) {
+        // Either way the caller may have stopped listening
+        match self {
+            Reply::Boxed(results) => {
+                let _ = results.send((target, status));
+            }
+            Reply::Typed(results) => {
+                let _ = results.send((target, status.map_err(QueryError::from_boxed)));
+            }
+        }
+    }
+}
+
+type Job = (Target, Reply);
+type DnsCache = Mutex<HashMap<(String, u16), Vec<SocketAddr>>>;
+
+pub struct ScanClient {
+    jobs: Option<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl ScanClient {
+    pub fn new(threads: usize) -> Self {
+        ScanClient::with_timeout(threads, TIMEOUT)
+    }
+
+    pub fn with_timeout(threads: usize, timeout: Duration) -> Self {
+        ScanClient::with_pacing(threads, timeout, Pacing::default())
+    }
+
+    pub fn with_rate_limit(threads: usize, timeout: Duration, limiter: RateLimiter) -> Self {
+        ScanClient::with_pacing(
+            threads,
+            timeout,
+            Pacing {
+                rate_limiter: Some(limiter),
+                ..Pacing::default()
+            },
+        )
+    }
+
+    pub fn with_pacing(threads: usize, timeout: Duration, pacing: Pacing) -> Self {
+        ScanClient::build(threads, timeout, pacing, NetworkFilter::default())
+    }
+
+    pub fn with_filter(
+        threads: usize,
+        timeout: Duration,
+        pacing: Pacing,
+        filter: NetworkFilter,
+    ) -> Self {
+        // Resolved addresses are checked before connecting, a target with none left fails with TargetDenied
+        ScanClient::build(threads, timeout, pacing, filter)
+    }
+
+    fn build(threads: usize, timeout: Duration, pacing: Pacing, filter: NetworkFilter) -> Self {
+        // Workers live as long as the client, so repeated scans reuse both the threads and the DNS cache.
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let dns_cache: Arc<DnsCache> = Arc::default();
+        let pacing = Arc::new(pacing);
+        let filter = Arc::new(filter);
+
+        let workers = (0..threads.max(1))
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                let dns_cache = Arc::clone(&dns_cache);
+                let pacing = Arc::clone(&pacing);
+                let filter = Arc::clone(&filter);
+                thread::spawn(move || loop {
+                    let job = receiver.lock().unwrap().recv(); // Lock is released before the query runs
+                    let (target, results) = match job {
+                        Ok(job) => job,
+                        Err(_) => break, // Client dropped
+                    };
+                    let status = scan_target(&target, timeout, &dns_cache, &pacing, &filter);
+                    results.send(target, status);
+                })
+            })
+            .collect();
+
+        ScanClient {
+            jobs: Some(jobs),
+            workers,
+        }
+    }
+
+    pub fn scan(
+        &self,
+        targets: impl IntoIterator<Item = Target>,
+    ) -> impl Iterator<Item = ScanResult> {
+        // Results are yielded as they complete, not in the order given
+        let (results, receiver) = mpsc::channel();
+        self.submit(targets, || Reply::Boxed(results.clone()));
+        receiver.into_iter() // Ends once every job has dropped its sender
+    }
+
+    pub fn scan_channel(
+        &self,
+        targets: impl IntoIterator<Item = Target>,
+    ) -> Receiver<TypedScanResult> {
+        // Same as scan with typed errors, the receiver can be handed to another thread and drained there
+        let (results, receiver) = mpsc::channel();
+        self.submit(targets, || Reply::Typed(results.clone()));
+        receiver
+    }
+
+    fn submit(&self, targets: impl IntoIterator<Item = Target>, reply: impl Fn() -> Reply) {
+        if let Some(jobs) = &self.jobs {
+            for target in targets {
+                let _ = jobs.send((target, reply()));
+            }
+        }
+    }
+}
+
+impl Drop for ScanClient {
+    fn drop(&mut self) {
+        self.jobs.take(); // Closing the channel stops the workers
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn scan_target(
+    target: &Target,
+    timeout: Duration,
+    dns_cache: &DnsCache,
+    pacing: &Arc<Pacing>,
+    filter: &NetworkFilter,
+) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+    let hostname = validate_address(&target.hostname, target.port)?;
+    let key = (hostname.clone(), target.port);
+
+    let cached = dns_cache.lock().unwrap().get(&key).cloned();
+    let socket_addrs = match cached {
+        Some(socket_addrs) => socket_addrs,
+        None => {
+            let socket_addrs = resolve(&hostname, target.port)?;
+            dns_cache.lock().unwrap().insert(key, socket_addrs.clone());
+            socket_addrs
+        }
+    };
+
+    let (socket_addrs, denied): (Vec<_>, Vec<_>) = socket_addrs
+        .into_iter()
+        .partition(|socket_addr| filter.permits(socket_addr.ip()));
+    if let (true, Some(socket_addr)) = (socket_addrs.is_empty(), denied.first()) {
+        return Err(QueryError::TargetDenied {
+            ip: socket_addr.ip(),
+        }
+        .into());
+    }
+
+    if let Some(jitter) = &pacing.jitter {
+        pacing.clock.sleep(random_between(jitter));
+    }
+
+    let query = StatusQuery::new(&hostname, target.port)
+        .timeout(timeout)
+        .socket_addrs(socket_addrs);
+    match pacing.rate_limiter {
+        Some(_) => {
+            // Limited per address dialed, a fallback address gets its own slot
+            let pacing = Arc::clone(pacing);
+            query
+                .connector(move |socket_addr, timeout| {
+                    if let Some(limiter) = &pacing.rate_limiter {
+                        limiter.wait(socket_addr.ip());
+                    }
+                    SocketOptions::default().connect(socket_addr, timeout)
+                })
+                .server_status()
+        }
+        None => query.server_status(),
+    }
+}
+
+fn random_between(range: &Range<Duration>) -> Duration {
+    // RandomState is randomly keyed, which is plenty for jitter and saves depending on rand
+    if range.is_empty() {
+        return range.start;
+    }
+    let random = RandomState::new().build_hasher().finish();
+    let span = (range.end - range.start).as_nanos() as u64;
+    range.start + Duration::from_nanos(random % span)
+}
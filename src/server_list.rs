@@ -0,0 +1,92 @@
+use crate::server_object::ServerStatus;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerListEntry {
+    pub name: String,
+    pub ip: String,           // As typed into the client, "host" or "host:port"
+    pub icon: Option<String>, // Base64 PNG without the data URI prefix, which is how servers.dat stores it
+}
+
+impl ServerStatus {
+    pub fn server_list_entry(&self, ip: &str) -> ServerListEntry {
+        // The client shows names on one line, so only the first line of the MOTD is used
+        let icon = self
+            .favicon
+            .strip_prefix("data:image/png;base64,")
+            .map(|icon| icon.split_whitespace().collect::<String>())
+            .filter(|icon| !icon.is_empty());
+        ServerListEntry {
+            name: self.description.first_line(),
+            ip: ip.to_string(),
+            icon,
+        }
+    }
+}
+
+#[cfg(feature = "nbt")]
+mod nbt {
+    use super::ServerListEntry;
+
+    const TAG_END: u8 = 0;
+    const TAG_STRING: u8 = 8;
+    const TAG_LIST: u8 = 9;
+    const TAG_COMPOUND: u8 = 10;
+
+    fn modified_utf8(string: &str) -> Vec<u8> {
+        // Java's DataOutput.writeUTF, NUL takes two bytes and anything outside the BMP is written
+        // as a surrogate pair of three byte sequences
+        let mut bytes = vec![];
+        for unit in string.encode_utf16() {
+            match unit {
+                0x0001..=0x007F => bytes.push(unit as u8),
+                0x0000 | 0x0080..=0x07FF => {
+                    bytes.push(0xC0 | (unit >> 6) as u8);
+                    bytes.push(0x80 | (unit & 0x3F) as u8);
+                }
+                _ => {
+                    bytes.push(0xE0 | (unit >> 12) as u8);
+                    bytes.push(0x80 | ((unit >> 6) & 0x3F) as u8);
+                    bytes.push(0x80 | (unit & 0x3F) as u8);
+                }
+            }
+        }
+        bytes
+    }
+
+    fn string(out: &mut Vec<u8>, value: &str) {
+        let bytes = modified_utf8(value);
+        let length = bytes.len().min(u16::MAX as usize); // writeUTF's limit, longer strings are cut
+        out.extend((length as u16).to_be_bytes());
+        out.extend(&bytes[..length]);
+    }
+
+    fn string_tag(out: &mut Vec<u8>, name: &str, value: &str) {
+        out.push(TAG_STRING);
+        string(out, name);
+        string(out, value);
+    }
+
+    pub fn servers_dat(entries: &[ServerListEntry]) -> Vec<u8> {
+        // Uncompressed NBT, the way the client writes servers.dat: a root compound holding a
+        // "servers" list of {name, ip, icon} compounds
+        let mut out = vec![TAG_COMPOUND];
+        string(&mut out, "");
+        out.push(TAG_LIST);
+        string(&mut out, "servers");
+        out.push(TAG_COMPOUND);
+        out.extend((entries.len() as i32).to_be_bytes());
+        for entry in entries {
+            string_tag(&mut out, "name", &entry.name);
+            string_tag(&mut out, "ip", &entry.ip);
+            if let Some(icon) = &entry.icon {
+                string_tag(&mut out, "icon", icon);
+            }
+            out.push(TAG_END);
+        }
+        out.push(TAG_END);
+        out
+    }
+}
+
+#[cfg(feature = "nbt")]
+pub use nbt::servers_dat;
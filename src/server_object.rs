@@ -1,12 +1,20 @@
-use serde::{Deserialize, Serialize};
+use serde::de::{IgnoredAny, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
 
-#[derive(Serialize, Deserialize)]
+use crate::MAX_SAMPLE_PLAYERS;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+#[serde(from = "ServerStatusRepr")]
 pub struct ServerStatus {
     #[serde(rename = "description")]
     pub description: Description,
 
     #[serde(rename = "favicon")]
-    #[serde(default)]
     pub favicon: String,
 
     #[serde(rename = "players")]
@@ -14,15 +22,496 @@ pub struct ServerStatus {
 
     #[serde(rename = "version")]
     pub version: Version,
+
+    #[serde(rename = "forgeData")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forge_data: Option<ForgeData>,
+
+    #[serde(rename = "modinfo")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modinfo: Option<ModInfo>,
+
+    #[serde(rename = "enforcesSecureChat")]
+    #[serde(default, deserialize_with = "loose_bool")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enforces_secure_chat: Option<bool>,
+
+    #[serde(rename = "previewsChat")]
+    #[serde(default, deserialize_with = "loose_bool")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previews_chat: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct ServerStatusRepr {
+    #[serde(rename = "description")]
+    description: Description,
+
+    #[serde(rename = "favicon")]
+    #[serde(default, deserialize_with = "loose_favicon")]
+    favicon: Option<String>,
+
+    // Some forks and proxies send "icon", only used when "favicon" is missing
+    #[serde(rename = "icon")]
+    #[serde(default, deserialize_with = "loose_favicon")]
+    icon: Option<String>,
+
+    #[serde(rename = "players")]
+    players: Players,
+
+    #[serde(rename = "version")]
+    version: Version,
+
+    #[serde(rename = "forgeData")]
+    #[serde(default)]
+    forge_data: Option<ForgeData>,
+
+    #[serde(rename = "modinfo")]
+    #[serde(default)]
+    modinfo: Option<ModInfo>,
+
+    #[serde(rename = "enforcesSecureChat")]
+    #[serde(default, deserialize_with = "loose_bool")]
+    enforces_secure_chat: Option<bool>,
+
+    #[serde(rename = "previewsChat")]
+    #[serde(default, deserialize_with = "loose_bool")]
+    previews_chat: Option<bool>,
+}
+
+impl From<ServerStatusRepr> for ServerStatus {
+    fn from(repr: ServerStatusRepr) -> Self {
+        ServerStatus {
+            description: repr.description,
+            favicon: repr.favicon.or(repr.icon).unwrap_or_default(),
+            players: repr.players,
+            version: repr.version,
+            forge_data: repr.forge_data,
+            modinfo: repr.modinfo,
+            enforces_secure_chat: repr.enforces_secure_chat,
+            previews_chat: repr.previews_chat,
+        }
+    }
+}
+
+fn loose_bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<bool>, D::Error> {
+    // Some compatibility layers (e.g. Geyser) send "true"/"false" strings, anything else counts as unset
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Bool(flag) => Some(flag),
+        serde_json::Value::String(flag) => flag.trim().to_ascii_lowercase().parse().ok(),
+        _ => None,
+    })
+}
+
+const FAVICON_KEYS: [&str; 3] = ["data", "url", "value"];
+
+pub(crate) fn nested_favicon(favicon: &serde_json::Value) -> Option<&str> {
+    // Some implementations wrap the data URI in an object, e.g. {"data": "data:image/png;base64,..."}
+    FAVICON_KEYS
+        .iter()
+        .find_map(|key| favicon.get(key).and_then(serde_json::Value::as_str))
+}
+
+fn loose_favicon<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    // Anything that isn't a string or a wrapped one counts as no favicon
+    let favicon = serde_json::Value::deserialize(deserializer)?;
+    Ok(Some(match &favicon {
+        serde_json::Value::String(favicon) => favicon.clone(),
+        favicon => nested_favicon(favicon).unwrap_or_default().to_string(),
+    }))
+}
+
+thread_local! {
+    static SAMPLE_CAP: Cell<usize> = const { Cell::new(MAX_SAMPLE_PLAYERS) };
+}
+
+pub(crate) fn sample_cap() -> usize {
+    SAMPLE_CAP.get()
+}
+
+struct RestoreSampleCap(usize);
+
+impl Drop for RestoreSampleCap {
+    fn drop(&mut self) {
+        SAMPLE_CAP.set(self.0);
+    }
+}
+
+pub(crate) fn with_sample_cap<T>(cap: usize, parse: impl FnOnce() -> T) -> T {
+    // Deserialize can't be handed options, so the cap is swapped in for this thread around the parse.
+    // The guard puts the previous cap back even if parse panics.
+    let _restore = RestoreSampleCap(SAMPLE_CAP.replace(cap));
+    parse()
 }
 
-#[derive(Serialize, Deserialize)]
+fn capped_sample<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Sample>, D::Error> {
+    struct CappedVisitor(usize);
+
+    impl<'de> Visitor<'de> for CappedVisitor {
+        type Value = Vec<Sample>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a list of sample players")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<Sample>, A::Error> {
+            // The size hint comes from the server, so nothing is preallocated from it
+            let mut sample = vec![];
+            while sample.len() < self.0 {
+                match seq.next_element()? {
+                    Some(player) => sample.push(player),
+                    None => return Ok(sample),
+                }
+            }
+            while seq.next_element::<IgnoredAny>()?.is_some() {} // Past the cap, skipped without being kept
+            Ok(sample)
+        }
+    }
+
+    deserializer.deserialize_seq(CappedVisitor(sample_cap()))
+}
+
+fn fnv1a(fields: &[&[u8]]) -> u64 {
+    fields
+        .iter()
+        .flat_map(|field| field.iter().chain(&[0xFF]))
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChatSecurity {
+    pub enforces_secure_chat: Option<bool>,
+    pub previews_chat: Option<bool>,
+}
+
+impl ServerStatus {
+    pub fn from_json(json: &str) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+        crate::parse_json(json)
+    }
+
+    pub fn identity_hash(&self) -> u64 {
+        self.identity_hash_with(&self.description.plain_text())
+    }
+
+    pub fn identity_hash_normalized(&self) -> u64 {
+        // identity_hash over Description::normalize_placeholders, so animated MOTDs keep one identity.
+        // A separate method since identity_hash values are stored and must not change.
+        self.identity_hash_with(&self.description.normalize_placeholders())
+    }
+
+    fn identity_hash_with(&self, motd: &str) -> u64 {
+        // FNV-1a over the fields that identify a server (not its population), stable across builds
+        // and Rust versions unlike DefaultHasher. Each field is terminated so they can't run together.
+        let protocol = self.version.protocol.to_be_bytes();
+        fnv1a(&[
+            motd.as_bytes(),
+            self.version.name.as_bytes(),
+            &protocol,
+            self.favicon.as_bytes(),
+        ])
+    }
+
+    pub fn chat_security(&self) -> ChatSecurity {
+        // Chat signing posture (1.19+), None when the server didn't say
+        ChatSecurity {
+            enforces_secure_chat: self.enforces_secure_chat,
+            previews_chat: self.previews_chat,
+        }
+    }
+
+    #[cfg(feature = "bincode")]
+    pub fn to_bincode(&self) -> Result<Vec<u8>, Box<dyn error::Error + Send + Sync>> {
+        // Compact cache format, far smaller than the JSON for large scan result stores
+        Ok(bincode::encode_to_vec(self, bincode::config::standard())?)
+    }
+
+    #[cfg(feature = "bincode")]
+    pub fn from_bincode(bytes: &[u8]) -> Result<ServerStatus, Box<dyn error::Error + Send + Sync>> {
+        let (status, _) = bincode::decode_from_slice(bytes, bincode::config::standard())?;
+        Ok(status)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
 pub struct Description {
     #[serde(rename = "text")]
+    #[serde(default)]
     pub text: String,
+
+    // The root is a styled component too, its style carries over to "extra"
+    #[serde(rename = "color")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+
+    #[serde(rename = "bold")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+
+    #[serde(rename = "italic")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+
+    #[serde(rename = "underlined")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub underlined: Option<bool>,
+
+    #[serde(rename = "strikethrough")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strikethrough: Option<bool>,
+
+    #[serde(rename = "obfuscated")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub obfuscated: Option<bool>,
+
+    #[serde(rename = "translate")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translate: Option<String>,
+
+    #[serde(rename = "with")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub with: Vec<Component>,
+
+    #[serde(rename = "extra")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extra: Vec<Component>,
+
+    #[serde(rename = "clickEvent")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub click_event: Option<ClickEvent>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+#[serde(from = "ComponentRepr")]
+pub struct Component {
+    #[serde(rename = "text")]
+    pub text: String,
+
+    #[serde(rename = "color")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+
+    #[serde(rename = "bold")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+
+    #[serde(rename = "italic")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+
+    #[serde(rename = "underlined")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub underlined: Option<bool>,
+
+    #[serde(rename = "strikethrough")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strikethrough: Option<bool>,
+
+    #[serde(rename = "obfuscated")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub obfuscated: Option<bool>,
+
+    #[serde(rename = "translate")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translate: Option<String>,
+
+    #[serde(rename = "with")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub with: Vec<Component>,
+
+    #[serde(rename = "extra")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extra: Vec<Component>,
+
+    #[serde(rename = "clickEvent")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub click_event: Option<ClickEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub struct ClickEvent {
+    // e.g. "open_url", "run_command" or "copy_to_clipboard"
+    #[serde(rename = "action")]
+    #[serde(default)]
+    pub action: String,
+
+    #[serde(rename = "value")]
+    #[serde(default)]
+    pub value: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ComponentRepr {
+    // Entries in "extra" may be full components or plain strings
+    Object {
+        #[serde(rename = "text")]
+        #[serde(default)]
+        text: String,
+
+        #[serde(rename = "color")]
+        #[serde(default)]
+        color: Option<String>,
+
+        #[serde(rename = "bold")]
+        #[serde(default)]
+        bold: Option<bool>,
+
+        #[serde(rename = "italic")]
+        #[serde(default)]
+        italic: Option<bool>,
+
+        #[serde(rename = "underlined")]
+        #[serde(default)]
+        underlined: Option<bool>,
+
+        #[serde(rename = "strikethrough")]
+        #[serde(default)]
+        strikethrough: Option<bool>,
+
+        #[serde(rename = "obfuscated")]
+        #[serde(default)]
+        obfuscated: Option<bool>,
+
+        #[serde(rename = "translate")]
+        #[serde(default)]
+        translate: Option<String>,
+
+        #[serde(rename = "with")]
+        #[serde(default)]
+        with: Vec<Component>,
+
+        #[serde(rename = "extra")]
+        #[serde(default)]
+        extra: Vec<Component>,
+
+        #[serde(rename = "clickEvent")]
+        #[serde(default)]
+        click_event: Option<ClickEvent>,
+    },
+    Text(String),
+}
+
+impl From<ComponentRepr> for Component {
+    fn from(repr: ComponentRepr) -> Self {
+        match repr {
+            ComponentRepr::Object {
+                text,
+                color,
+                bold,
+                italic,
+                underlined,
+                strikethrough,
+                obfuscated,
+                translate,
+                with,
+                extra,
+                click_event,
+            } => Component {
+                text,
+                color,
+                bold,
+                italic,
+                underlined,
+                strikethrough,
+                obfuscated,
+                translate,
+                with,
+                extra,
+                click_event,
+            },
+            ComponentRepr::Text(text) => Component {
+                text,
+                color: None,
+                bold: None,
+                italic: None,
+                underlined: None,
+                strikethrough: None,
+                obfuscated: None,
+                translate: None,
+                with: vec![],
+                extra: vec![],
+                click_event: None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub struct ForgeData {
+    // Sent by Forge 1.13+ servers
+    #[serde(rename = "fmlNetworkVersion")]
+    #[serde(default)]
+    pub fml_network_version: i64,
+
+    #[serde(rename = "mods")]
+    #[serde(default)]
+    pub mods: Vec<ForgeMod>,
+
+    #[serde(rename = "truncated")]
+    #[serde(default)]
+    pub truncated: bool,
+
+    #[serde(rename = "d")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub d: Option<String>, // FML3 packed mod and channel list, decoded by all_mods
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub struct ForgeMod {
+    #[serde(rename = "modId")]
+    pub mod_id: String,
+
+    #[serde(rename = "modmarker")]
+    #[serde(default)]
+    pub mod_marker: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub struct ModInfo {
+    // Sent by Forge 1.7 - 1.12 servers
+    #[serde(rename = "type")]
+    #[serde(default)]
+    pub mod_type: String,
+
+    #[serde(rename = "modList")]
+    #[serde(default)]
+    pub mod_list: Vec<ModInfoEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+pub struct ModInfoEntry {
+    #[serde(rename = "modid")]
+    pub mod_id: String,
+
+    #[serde(rename = "version")]
+    #[serde(default)]
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
 pub struct Players {
     #[serde(rename = "max")]
     pub max: i64,
@@ -31,20 +520,146 @@ pub struct Players {
     pub online: i64,
 
     #[serde(rename = "sample")]
-    #[serde(default)]
+    #[serde(default, deserialize_with = "capped_sample")]
+    // At most MAX_SAMPLE_PLAYERS unless configured
     pub sample: Vec<Sample>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Sample {
     #[serde(rename = "id")]
     pub id: String,
 
     #[serde(rename = "name")]
+    #[serde(deserialize_with = "component_or_string")]
     pub name: String,
+
+    // Anything else a proxy decorates the entry with (prefixes, display names, ...)
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+fn component_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    // Some networks send the name as a chat component, it's flattened to its text
+    fn flatten(component: &Component) -> String {
+        let mut text = component.text.clone();
+        for child in &component.extra {
+            text.push_str(&flatten(child));
+        }
+        text
+    }
+    Ok(flatten(&Component::deserialize(deserializer)?))
+}
+
+#[cfg(feature = "bincode")]
+impl bincode::Encode for Sample {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        // serde_json::Value has no bincode impls, so the extra fields are stored as a JSON string
+        let extra = serde_json::to_string(&self.extra)
+            .map_err(|error| bincode::error::EncodeError::OtherString(error.to_string()))?;
+        self.id.encode(encoder)?;
+        self.name.encode(encoder)?;
+        extra.encode(encoder)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<Context> bincode::Decode<Context> for Sample {
+    fn decode<D: bincode::de::Decoder<Context = Context>>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        let id = String::decode(decoder)?;
+        let name = String::decode(decoder)?;
+        let extra = serde_json::from_str(&String::decode(decoder)?)
+            .map_err(|error| bincode::error::DecodeError::OtherString(error.to_string()))?;
+        Ok(Sample { id, name, extra })
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+#[cfg(feature = "bincode")]
+bincode::impl_borrow_decode!(Sample);
+
+impl Sample {
+    pub fn uuid_version(&self) -> Option<u8> {
+        // Version nibble of the UUID, None when id isn't a UUID, is the nil UUID placeholder lines use,
+        // or carries a version outside the 1-8 RFC 9562 defines
+        let hex: String = self.id.chars().filter(|char| *char != '-').collect();
+        if hex.len() != 32 || !hex.chars().all(|char| char.is_ascii_hexdigit()) {
+            return None;
+        }
+        if hex.chars().all(|char| char == '0') {
+            return None;
+        }
+        u8::from_str_radix(&hex[12..13], 16)
+            .ok()
+            .filter(|version| (1..=8).contains(version))
+    }
+
+    pub fn is_real_player(&self) -> bool {
+        // Hidden players show up as "Anonymous Player" (1.19+), and servers pad the sample with
+        // informational or obfuscated lines that carry formatting codes, a nil UUID or an id that
+        // isn't a UUID at all (advertising lines often use random strings).
+        self.uuid_version().is_some() && self.name != "Anonymous Player" && !self.name.contains('§')
+    }
+}
+
+impl Players {
+    pub fn fill_ratio(&self) -> Option<f64> {
+        // Not clamped, servers with reserved slots report more online than max. None without a usable max.
+        if self.max <= 0 || self.online < 0 {
+            return None;
+        }
+        Some(self.online as f64 / self.max as f64)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.fill_ratio().is_some_and(|ratio| ratio >= 1.0)
+    }
+
+    pub fn real_players(&self) -> Vec<&Sample> {
+        self.sample
+            .iter()
+            .filter(|sample| sample.is_real_player())
+            .collect()
+    }
+
+    pub fn named_sample(&self) -> (Vec<&Sample>, i64) {
+        // Sample entries that are real players, and how many online players that leaves unlisted
+        let named: Vec<&Sample> = self
+            .sample
+            .iter()
+            .filter(|sample| sample.is_real_player())
+            .collect();
+        let hidden_count = (self.online - named.len() as i64).max(0);
+        (named, hidden_count)
+    }
+
+    pub fn likely_online_mode(&self) -> Option<bool> {
+        // Mojang accounts have random (version 4) UUIDs, offline mode derives version 3 UUIDs from the name.
+        // Best effort, a sample that is empty or mixes both is ambiguous.
+        let versions: Vec<u8> = self
+            .sample
+            .iter()
+            .filter_map(Sample::uuid_version)
+            .collect();
+        let online = versions.iter().filter(|version| **version == 4).count();
+        let offline = versions.iter().filter(|version| **version == 3).count();
+
+        match (online, offline) {
+            (0, 0) => None,
+            (_, 0) => Some(true),
+            (0, _) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+#[serde(from = "VersionRepr")]
 pub struct Version {
     #[serde(rename = "name")]
     pub name: String,
@@ -52,3 +667,100 @@ pub struct Version {
     #[serde(rename = "protocol")]
     pub protocol: i64,
 }
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum VersionRepr {
+    // Some servers omit fields or send the version as a bare string, neither should fail the whole status.
+    // Multi-version proxies may send a list, the first entry is the primary one.
+    Object {
+        #[serde(rename = "name")]
+        #[serde(default)]
+        name: String,
+
+        #[serde(rename = "protocol")]
+        #[serde(default)]
+        protocol: i64,
+    },
+    Name(String),
+    List(Vec<VersionRepr>),
+}
+
+impl From<VersionRepr> for Version {
+    fn from(repr: VersionRepr) -> Self {
+        match repr {
+            VersionRepr::Object { name, protocol } => Version { name, protocol },
+            VersionRepr::Name(name) => Version { name, protocol: 0 },
+            VersionRepr::List(versions) => match versions.into_iter().next() {
+                Some(primary) => primary.into(),
+                None => Version {
+                    name: String::new(),
+                    protocol: 0,
+                },
+            },
+        }
+    }
+}
+
+impl Version {
+    pub fn release_name(&self) -> &str {
+        // Prefer the release the protocol number maps to, servers often put marketing strings in "name".
+        protocol_to_version(self.protocol).unwrap_or(&self.name)
+    }
+}
+
+pub fn protocol_to_version(protocol: i64) -> Option<&'static str> {
+    // Maps release protocol numbers to the newest release using them, https://wiki.vg/Protocol_version_numbers
+    let version = match protocol {
+        4 => "1.7.5",
+        5 => "1.7.10",
+        47 => "1.8.9",
+        107 => "1.9",
+        108 => "1.9.1",
+        109 => "1.9.2",
+        110 => "1.9.4",
+        210 => "1.10.2",
+        315 => "1.11",
+        316 => "1.11.2",
+        335 => "1.12",
+        338 => "1.12.1",
+        340 => "1.12.2",
+        393 => "1.13",
+        401 => "1.13.1",
+        404 => "1.13.2",
+        477 => "1.14",
+        480 => "1.14.1",
+        485 => "1.14.2",
+        490 => "1.14.3",
+        498 => "1.14.4",
+        573 => "1.15",
+        575 => "1.15.1",
+        578 => "1.15.2",
+        735 => "1.16",
+        736 => "1.16.1",
+        751 => "1.16.2",
+        753 => "1.16.3",
+        754 => "1.16.5",
+        755 => "1.17",
+        756 => "1.17.1",
+        757 => "1.18.1",
+        758 => "1.18.2",
+        759 => "1.19",
+        760 => "1.19.2",
+        761 => "1.19.3",
+        762 => "1.19.4",
+        763 => "1.20.1",
+        764 => "1.20.2",
+        765 => "1.20.4",
+        766 => "1.20.6",
+        767 => "1.21.1",
+        768 => "1.21.3",
+        769 => "1.21.4",
+        770 => "1.21.5",
+        771 => "1.21.6",
+        772 => "1.21.8",
+        773 => "1.21.10",
+        _ => return None,
+    };
+    Some(version)
+}
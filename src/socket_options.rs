@@ -0,0 +1,64 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+use socket2::{Domain, Protocol, Socket, Type};
+
+pub type Connector = dyn Fn(&SocketAddr, Duration) -> io::Result<TcpStream> + Send + Sync;
+
+#[derive(Default, Clone)]
+pub(crate) struct SocketOptions {
+    pub(crate) recv_buffer_size: Option<usize>,
+    pub(crate) send_buffer_size: Option<usize>,
+    pub(crate) ttl: Option<u32>,
+    pub(crate) source_port: Option<u16>,
+    pub(crate) connector: Option<Arc<Connector>>,
+}
+
+impl SocketOptions {
+    pub(crate) fn connect(
+        &self,
+        socket_addr: &SocketAddr,
+        timeout: Duration,
+    ) -> io::Result<TcpStream> {
+        // A custom connector takes over entirely, socket options are then its responsibility
+        if let Some(connector) = &self.connector {
+            return connector(socket_addr, timeout);
+        }
+
+        let socket = self.socket(socket_addr)?;
+        socket.connect_timeout(&(*socket_addr).into(), timeout)?;
+        Ok(socket.into())
+    }
+
+    pub(crate) fn socket(&self, socket_addr: &SocketAddr) -> io::Result<Socket> {
+        // Options are applied before connecting, unset ones keep the OS defaults
+        let socket = Socket::new(
+            Domain::for_address(*socket_addr),
+            Type::STREAM,
+            Some(Protocol::TCP),
+        )?;
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+        match (self.ttl, socket_addr) {
+            (Some(ttl), SocketAddr::V4(_)) => socket.set_ttl_v4(ttl)?,
+            (Some(hops), SocketAddr::V6(_)) => socket.set_unicast_hops_v6(hops)?,
+            (None, _) => {}
+        }
+        if let Some(port) = self.source_port {
+            // Reusing the address lets the next query bind the port while the last one is in TIME_WAIT
+            let unspecified = match socket_addr {
+                SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            };
+            socket.set_reuse_address(true)?;
+            socket.bind(&SocketAddr::new(unspecified, port).into())?;
+        }
+        Ok(socket)
+    }
+}
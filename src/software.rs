@@ -0,0 +1,99 @@
+use crate::server_object::ServerStatus;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerSoftware {
+    Velocity,
+    BungeeCord,
+    Waterfall,
+    Paper,
+    Purpur,
+    Spigot,
+    Forge,
+    Geyser,
+    Vanilla,
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeyserInfo {
+    pub geyser_version: Option<String>,
+    pub bedrock_versions: Option<String>, // Supported Bedrock range, e.g. "1.20.40 - 1.20.50"
+}
+
+impl ServerSoftware {
+    pub fn is_proxy(&self) -> bool {
+        matches!(
+            self,
+            ServerSoftware::Velocity | ServerSoftware::BungeeCord | ServerSoftware::Waterfall
+        )
+    }
+}
+
+impl ServerStatus {
+    pub fn detect_software(&self) -> ServerSoftware {
+        // Best effort, from the fingerprints different software leaves in its status
+        if self.forge_data.is_some()
+            || self
+                .modinfo
+                .as_ref()
+                .is_some_and(|modinfo| modinfo.mod_type == "FML")
+        {
+            return ServerSoftware::Forge;
+        }
+
+        let name = self.version.name.to_lowercase();
+        let fingerprints = [
+            ("velocity", ServerSoftware::Velocity),
+            ("waterfall", ServerSoftware::Waterfall),
+            ("bungeecord", ServerSoftware::BungeeCord),
+            ("geyser", ServerSoftware::Geyser),
+            ("purpur", ServerSoftware::Purpur),
+            ("paper", ServerSoftware::Paper),
+            ("spigot", ServerSoftware::Spigot),
+            ("craftbukkit", ServerSoftware::Spigot),
+        ];
+        if let Some((_, software)) = fingerprints
+            .iter()
+            .find(|(fingerprint, _)| name.contains(fingerprint))
+        {
+            return *software;
+        }
+
+        // Vanilla only ever sends the bare release, like "1.20.4"
+        let is_release = !name.is_empty()
+            && name
+                .split('.')
+                .all(|part| !part.is_empty() && part.chars().all(|char| char.is_ascii_digit()));
+        if is_release {
+            return ServerSoftware::Vanilla;
+        }
+        ServerSoftware::Unknown
+    }
+
+    pub fn geyser_info(&self) -> Option<GeyserInfo> {
+        // Geyser only identifies itself in the version name, e.g. "Geyser 2.2.0 (1.20.40 - 1.20.50)".
+        // Any extra keys it adds are ignored by the parser.
+        if self.detect_software() != ServerSoftware::Geyser {
+            return None;
+        }
+        let name = &self.version.name;
+        let geyser_version = name
+            .split('(')
+            .next()
+            .and_then(|prefix| {
+                prefix
+                    .split_whitespace()
+                    .find(|word| word.starts_with(|char: char| char.is_ascii_digit()))
+            })
+            .map(str::to_string);
+        let bedrock_versions = name
+            .split_once('(')
+            .and_then(|(_, rest)| rest.split_once(')'))
+            .map(|(versions, _)| versions.trim().to_string())
+            .filter(|versions| !versions.is_empty());
+        Some(GeyserInfo {
+            geyser_version,
+            bedrock_versions,
+        })
+    }
+}
@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+
+use crate::server_object::{protocol_to_version, ServerStatus};
+
+// Flat representation for APIs. Unlike ServerStatus, which follows the wire format, fields here
+// are only ever added, never renamed or retyped. Breaking changes get a StatusV2.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusV1 {
+    #[serde(rename = "schema_version")]
+    pub schema_version: u32,
+
+    #[serde(rename = "motd")]
+    pub motd: String,
+
+    #[serde(rename = "favicon")]
+    pub favicon: Option<String>,
+
+    #[serde(rename = "players_online")]
+    pub players_online: i64,
+
+    #[serde(rename = "players_max")]
+    pub players_max: i64,
+
+    #[serde(rename = "player_sample")]
+    pub player_sample: Vec<PlayerV1>,
+
+    #[serde(rename = "version_name")]
+    pub version_name: String,
+
+    #[serde(rename = "version_protocol")]
+    pub version_protocol: i64,
+
+    #[serde(rename = "release_name")]
+    pub release_name: Option<String>,
+
+    #[serde(rename = "mods")]
+    pub mods: Vec<ModV1>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerV1 {
+    #[serde(rename = "id")]
+    pub id: String,
+
+    #[serde(rename = "name")]
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModV1 {
+    #[serde(rename = "id")]
+    pub id: String,
+
+    #[serde(rename = "version")]
+    pub version: Option<String>,
+}
+
+impl From<ServerStatus> for StatusV1 {
+    fn from(status: ServerStatus) -> Self {
+        // forgeData (1.13+) only carries markers, modinfo (older Forge) carries real versions
+        let forge_mods = status.forge_data.into_iter().flat_map(|forge_data| {
+            let mods = forge_data.all_mods().unwrap_or(forge_data.mods); // A broken "d" keeps the plain list
+            mods.into_iter().map(|forge_mod| ModV1 {
+                id: forge_mod.mod_id,
+                version: Some(forge_mod.mod_marker).filter(|marker| !marker.is_empty()),
+            })
+        });
+        let modinfo_mods = status.modinfo.into_iter().flat_map(|modinfo| {
+            modinfo.mod_list.into_iter().map(|entry| ModV1 {
+                id: entry.mod_id,
+                version: Some(entry.version).filter(|version| !version.is_empty()),
+            })
+        });
+
+        StatusV1 {
+            schema_version: 1,
+            motd: status.description.plain_text(),
+            favicon: Some(status.favicon).filter(|favicon| !favicon.is_empty()),
+            players_online: status.players.online,
+            players_max: status.players.max,
+            player_sample: status
+                .players
+                .sample
+                .into_iter()
+                .map(|sample| PlayerV1 {
+                    id: sample.id,
+                    name: sample.name,
+                })
+                .collect(),
+            release_name: protocol_to_version(status.version.protocol).map(str::to_string),
+            version_name: status.version.name,
+            version_protocol: status.version.protocol,
+            mods: forge_mods.chain(modinfo_mods).collect(),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use std::error;
+use std::io::{self, Read};
+use std::time::Duration;
+
+use crate::clock::Clock;
+use crate::QueryError;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Timings {
+    pub resolve: Duration,
+    pub connect: Duration,
+    pub write: Duration,
+    pub read: Duration,
+    pub parse: Duration,
+    pub bytes_sent: usize,
+    pub bytes_received: usize,
+    pub frame_length: usize, // Outer length prefix of the status packet
+}
+
+pub(crate) fn timed<T>(clock: &dyn Clock, phase: &mut Duration, f: impl FnOnce() -> T) -> T {
+    // Runs f and adds how long it took to the given phase
+    let start = clock.now();
+    let result = f();
+    *phase += clock.now() - start;
+    result
+}
+
+pub(crate) struct CountingReader<R> {
+    inner: R,
+    pub(crate) count: usize,
+    reads: usize,
+    max_reads: Option<usize>,
+    exceeded: bool,
+}
+
+impl<R> CountingReader<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        CountingReader {
+            inner,
+            count: 0,
+            reads: 0,
+            max_reads: None,
+            exceeded: false,
+        }
+    }
+
+    pub(crate) fn limit_reads(&mut self, max_reads: Option<usize>) {
+        // Only reads from here on count. Set once the header is in, its VarInts are read a byte at a time.
+        self.reads = 0;
+        self.max_reads = max_reads;
+    }
+
+    pub(crate) fn limited<T>(
+        &self,
+        result: Result<T, Box<dyn error::Error + Send + Sync>>,
+    ) -> Result<T, Box<dyn error::Error + Send + Sync>> {
+        // The refused read may have been wrapped (e.g. by serde_json) on its way up, so the typed
+        // error is restored from here
+        match self.max_reads {
+            Some(max_reads) if self.exceeded && result.is_err() => {
+                Err(QueryError::TooManyReads { max_reads }.into())
+            }
+            _ => result,
+        }
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // A server trickling a byte at a time can otherwise keep us reading for as long as each read beats the timeout
+        if self
+            .max_reads
+            .is_some_and(|max_reads| self.reads >= max_reads)
+        {
+            self.exceeded = true;
+            return Err(io::Error::other("Too many reads for a single response"));
+        }
+        self.reads += 1;
+        let read = self.inner.read(buf)?;
+        self.count += read;
+        Ok(read)
+    }
+}
@@ -0,0 +1,45 @@
+use std::io::{self, Read, Write};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Transcript {
+    pub sent: Vec<u8>,     // Everything written, PROXY header and handshake included
+    pub received: Vec<u8>, // Everything read, in the order it arrived
+}
+
+pub(crate) struct Recorder<S> {
+    inner: S,
+    pub(crate) transcript: Option<Transcript>, // None when capturing is off, so nothing is copied
+}
+
+impl<S> Recorder<S> {
+    pub(crate) fn new(inner: S, capture: bool) -> Self {
+        Recorder {
+            inner,
+            transcript: capture.then(Transcript::default),
+        }
+    }
+}
+
+impl<S: Read> Read for Recorder<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if let Some(transcript) = &mut self.transcript {
+            transcript.received.extend_from_slice(&buf[..read]);
+        }
+        Ok(read)
+    }
+}
+
+impl<S: Write> Write for Recorder<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if let Some(transcript) = &mut self.transcript {
+            transcript.sent.extend_from_slice(&buf[..written]);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::error;
+use std::net::UdpSocket;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::ports::{QueryPort, StatusPort};
+use crate::server_object::ServerStatus;
+use crate::{resolve, validate_address, StatusQuery, TIMEOUT};
+
+// UDP Query protocol (enable-query in server.properties), https://wiki.vg/Query
+const MAGIC: [u8; 2] = [0xFE, 0xFD];
+const HANDSHAKE: u8 = 0x09;
+const STAT: u8 = 0x00;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FullStat {
+    pub motd: String,
+    pub game_type: String,
+    pub game_id: String,
+    pub version: String,
+    pub server_mod: String,
+    pub plugins: Vec<String>,
+    pub map: String,
+    pub num_players: i64,
+    pub max_players: i64,
+    pub host_port: u16,
+    pub host_ip: String,
+    pub players: Vec<String>,
+}
+
+pub struct ServerInfo {
+    pub status: ServerStatus,
+    pub query: Option<FullStat>, // None when query is disabled or unreachable
+}
+
+pub fn query_full_stat(
+    hostname: &str,
+    port: impl Into<QueryPort>,
+    timeout: Duration,
+) -> Result<FullStat, Box<dyn error::Error + Send + Sync>> {
+    let QueryPort(port) = port.into();
+    let hostname = validate_address(hostname, port)?;
+    let socket_addr = *resolve(&hostname, port)?
+        .first()
+        .ok_or("Failed to parse hostname")?;
+
+    let bind_addr = if socket_addr.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = UdpSocket::bind(bind_addr)?;
+    socket.set_read_timeout(Some(timeout))?;
+    socket.connect(socket_addr)?;
+
+    let session_id = (SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos() as i32)
+        & 0x0F0F0F0F; // Only the low 4 bits of each byte are used
+
+    // Handshake, the server answers with a challenge token as a decimal string
+    socket.send(&[&MAGIC[..], &[HANDSHAKE], &session_id.to_be_bytes()].concat())?;
+    let mut buffer = [0; 65535];
+    let length = socket.recv(&mut buffer)?;
+    let token = read_packet(&buffer[..length], HANDSHAKE, session_id)?;
+    let token: i32 = cstring(token)?.0.trim().parse()?;
+
+    // Full stat request, padded with 4 bytes so it isn't mistaken for a basic stat
+    socket.send(
+        &[
+            &MAGIC[..],
+            &[STAT],
+            &session_id.to_be_bytes(),
+            &token.to_be_bytes(),
+            &[0x00; 4],
+        ]
+        .concat(),
+    )?;
+    let length = socket.recv(&mut buffer)?;
+    parse_full_stat(read_packet(&buffer[..length], STAT, session_id)?)
+}
+
+pub fn server_info(
+    hostname: &str,
+    status_port: impl Into<StatusPort>,
+    query_port: impl Into<QueryPort>,
+) -> Result<ServerInfo, Box<dyn error::Error + Send + Sync>> {
+    // The TCP status is required, the UDP query is optional since most servers leave it disabled
+    let status = StatusQuery::new(hostname, status_port).server_status()?;
+    let query = query_full_stat(hostname, query_port, TIMEOUT).ok();
+    Ok(ServerInfo { status, query })
+}
+
+fn read_packet(
+    packet: &[u8],
+    packet_type: u8,
+    session_id: i32,
+) -> Result<&[u8], Box<dyn error::Error + Send + Sync>> {
+    // Responses start with the type and the session id we sent
+    if packet.len() < 5 || packet[0] != packet_type || packet[1..5] != session_id.to_be_bytes() {
+        return Err("Unexpected query response".into());
+    }
+    Ok(&packet[5..])
+}
+
+fn cstring(bytes: &[u8]) -> Result<(String, &[u8]), Box<dyn error::Error + Send + Sync>> {
+    // Null terminated string and whatever follows it
+    let end = bytes
+        .iter()
+        .position(|byte| *byte == 0)
+        .ok_or("Unterminated string in query response")?;
+    Ok((
+        String::from_utf8_lossy(&bytes[..end]).into_owned(),
+        &bytes[end + 1..],
+    ))
+}
+
+fn parse_full_stat(payload: &[u8]) -> Result<FullStat, Box<dyn error::Error + Send + Sync>> {
+    // 11 bytes of padding, key/value pairs ending with an empty key, 10 more bytes of padding,
+    // then player names ending with an empty name.
+    let mut rest = payload.get(11..).ok_or("Query response too short")?;
+    let mut values = HashMap::new();
+    loop {
+        let (key, after_key) = cstring(rest)?;
+        if key.is_empty() {
+            rest = after_key;
+            break;
+        }
+        let (value, after_value) = cstring(after_key)?;
+        values.insert(key, value);
+        rest = after_value;
+    }
+
+    rest = rest.get(10..).ok_or("Query response too short")?;
+    let mut players = vec![];
+    loop {
+        let (player, after_player) = cstring(rest)?;
+        if player.is_empty() {
+            break;
+        }
+        players.push(player);
+        rest = after_player;
+    }
+
+    let value = |key: &str| values.get(key).cloned().unwrap_or_default();
+    // "plugins" is "<server mod>: <plugin>; <plugin>" or just the server mod
+    let plugins = value("plugins");
+    let (server_mod, plugins) = match plugins.split_once(": ") {
+        Some((server_mod, plugins)) => (
+            server_mod.to_string(),
+            plugins.split("; ").map(str::to_string).collect(),
+        ),
+        None => (plugins, vec![]),
+    };
+
+    Ok(FullStat {
+        motd: value("hostname"),
+        game_type: value("gametype"),
+        game_id: value("game_id"),
+        version: value("version"),
+        server_mod,
+        plugins,
+        map: value("map"),
+        num_players: value("numplayers").parse().unwrap_or(0),
+        max_players: value("maxplayers").parse().unwrap_or(0),
+        host_port: value("hostport").parse().unwrap_or(0),
+        host_ip: value("hostip"),
+        players,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_stat() {
+        let mut payload = b"splitnum\0\x80\0".to_vec();
+        for (key, value) in [
+            ("hostname", "A Minecraft Server"),
+            ("gametype", "SMP"),
+            ("numplayers", "2"),
+            ("maxplayers", "20"),
+            ("plugins", "Paper on 1.20.4: WorldEdit 7.2; Essentials 2.20"),
+        ] {
+            payload.extend([key.as_bytes(), b"\0", value.as_bytes(), b"\0"].concat());
+        }
+        payload.extend(b"\0\x01player_\0\0Notch\0jeb_\0\0");
+
+        let stat = parse_full_stat(&payload).unwrap();
+        assert_eq!(stat.motd, "A Minecraft Server");
+        assert_eq!(stat.num_players, 2);
+        assert_eq!(stat.server_mod, "Paper on 1.20.4");
+        assert_eq!(stat.plugins, ["WorldEdit 7.2", "Essentials 2.20"]);
+        assert_eq!(stat.players, ["Notch", "jeb_"]);
+        assert!(parse_full_stat(b"short").is_err());
+    }
+}
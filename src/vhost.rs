@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+use std::error;
+use std::thread;
+
+use crate::ports::StatusPort;
+use crate::server_object::ServerStatus;
+use crate::{resolve, validate_address, QueryError, StatusQuery};
+
+pub type VhostResults = HashMap<String, Result<ServerStatus, Box<dyn error::Error + Send + Sync>>>;
+
+pub fn query_vhosts(
+    proxy_addr: &str,
+    vhosts: &[&str],
+    port: impl Into<StatusPort>,
+) -> Result<VhostResults, QueryError> {
+    // Asks one proxy for each virtual host's status by changing only the handshake hostname, the
+    // proxy routes each to its backend. The proxy is resolved once and the queries run in parallel,
+    // a failing vhost only fails its own entry.
+    let StatusPort(port) = port.into();
+    let hostname = validate_address(proxy_addr, port)?;
+    let socket_addrs = resolve(&hostname, port)?;
+
+    Ok(thread::scope(|scope| {
+        let queries = vhosts
+            .iter()
+            .map(|vhost| {
+                let query = StatusQuery::new(&hostname, port)
+                    .handshake_hostname(vhost)
+                    .socket_addrs(socket_addrs.clone());
+                (vhost, scope.spawn(move || query.server_status()))
+            })
+            .collect::<Vec<_>>();
+        queries
+            .into_iter()
+            .map(|(vhost, query)| {
+                let status = query
+                    .join()
+                    .unwrap_or_else(|_| Err("Query thread panicked".into()));
+                (vhost.to_string(), status)
+            })
+            .collect()
+    }))
+}